@@ -0,0 +1,11 @@
+use std::process::ExitCode;
+
+use clap::Parser;
+use ui::cli::{
+    build::{run, BuildArgs},
+    report,
+};
+
+fn main() -> ExitCode {
+    report(run(BuildArgs::parse()))
+}
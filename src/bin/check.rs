@@ -0,0 +1,11 @@
+use std::process::ExitCode;
+
+use clap::Parser;
+use ui::cli::{
+    check::{run, CheckArgs},
+    report,
+};
+
+fn main() -> ExitCode {
+    report(run(CheckArgs::parse()))
+}
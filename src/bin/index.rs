@@ -0,0 +1,11 @@
+use std::process::ExitCode;
+
+use clap::Parser;
+use ui::cli::{
+    indexer::{run, IndexArgs},
+    report,
+};
+
+fn main() -> ExitCode {
+    report(run(IndexArgs::parse()))
+}
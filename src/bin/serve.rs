@@ -0,0 +1,11 @@
+use std::process::ExitCode;
+
+use clap::Parser;
+use ui::cli::{
+    report,
+    serve::{run, ServeArgs},
+};
+
+fn main() -> ExitCode {
+    report(run(ServeArgs::parse()))
+}
@@ -0,0 +1,196 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use super::{
+    config::ProjectConfig,
+    content::ContentDir,
+    sitemap::{front_matter_lastmod, robots_txt, sitemap_xml, SitemapEntry},
+    write_file, CliError,
+};
+use crate::{
+    utils::{
+        front_matter::split_front_matter,
+        head::PageMeta,
+        render::{summarize, RenderContext, RenderOptions},
+        routing::{site_url, HASH_ROUTING},
+    },
+    BASE_URL,
+};
+
+/// Writes `sitemap.xml`, `robots.txt` and a pre-rendered `index.html` per
+/// page into the app's `out_dir`.
+#[derive(Debug, Parser)]
+#[command(name = "blogy-build")]
+pub struct BuildArgs {
+    /// Local checkout of the content repository, the folder holding index.json
+    #[arg(long)]
+    pub content: PathBuf,
+    /// Origin the site is published at, e.g. https://amaali7.github.io
+    #[arg(long)]
+    pub site_url: String,
+    /// Folder holding Dioxus.toml
+    #[arg(long, default_value = ".")]
+    pub project: PathBuf,
+    /// Write here instead of the out_dir from Dioxus.toml
+    #[arg(long)]
+    pub out_dir: Option<PathBuf>,
+    /// The app was built with the `hash-router` feature: pages live in the
+    /// URL fragment, so no per-page files are written
+    #[arg(long, default_value_t = HASH_ROUTING)]
+    pub hash_router: bool,
+}
+
+pub fn run(args: BuildArgs) -> Result<(), CliError> {
+    let config = ProjectConfig::load(&args.project)?;
+    let out_dir = args.out_dir.unwrap_or_else(|| config.out_dir.clone());
+    let content = ContentDir::open(&args.content)?;
+    let db = content.load_db()?;
+    let render = RenderContext::new(
+        BASE_URL,
+        RenderOptions {
+            base_path: config.base_prefix(),
+            hash_routing: args.hash_router,
+            ..RenderOptions::default()
+        },
+    );
+
+    let site_root = format!(
+        "{}{}",
+        args.site_url.trim_end_matches('/'),
+        config.base_prefix()
+    );
+    // Pages are only pre-rendered once `dx bundle` has produced the shell,
+    // and only where the host serves them: with hash routing every page is
+    // the shell at the site root
+    let template = std::fs::read_to_string(out_dir.join("index.html"))
+        .ok()
+        .filter(|_| !args.hash_router);
+
+    let mut entries = Vec::new();
+    let mut baked = 0;
+    for path in db.site_pages() {
+        let Some(file) = content.page_file(&db, &path) else {
+            continue;
+        };
+        let markdown = match std::fs::read_to_string(&file) {
+            Ok(markdown) => markdown,
+            Err(e) => {
+                eprintln!("warning: skipping {path}: {}: {e}", file.display());
+                continue;
+            }
+        };
+        let (front_matter, body) = split_front_matter(&markdown);
+        let url = site_url(&site_root, &path, args.hash_router);
+
+        if front_matter.get_bool("sitemap") != Some(false) {
+            entries.push(SitemapEntry {
+                url: url.clone(),
+                lastmod: front_matter_lastmod(&front_matter)
+                    .or_else(|| content.last_commit_date(&file)),
+            });
+        }
+
+        if let Some(template) = &template {
+            let summary = summarize(body, render.options());
+            let meta = PageMeta::from_page(
+                db.page_name(&path).unwrap_or_default(),
+                &front_matter,
+                &summary.excerpt,
+                Some(url),
+                |image| render.image_url(image, &path),
+            );
+            let target = out_dir
+                .join(path.trim_start_matches('/'))
+                .join("index.html");
+            write_file(&target, &bake(template, &meta))?;
+            baked += 1;
+        }
+    }
+
+    write_file(&out_dir.join("sitemap.xml"), &sitemap_xml(&entries))?;
+    write_file(
+        &out_dir.join("robots.txt"),
+        &robots_txt(&format!("{site_root}/sitemap.xml")),
+    )?;
+
+    println!(
+        "Wrote sitemap.xml with {} pages, robots.txt and {baked} pre-rendered pages to {}",
+        entries.len(),
+        out_dir.display()
+    );
+    Ok(())
+}
+
+/// The app shell with its static `<title>` replaced by the page's head tags.
+fn bake(template: &str, meta: &PageMeta) -> String {
+    let head = meta.head_html();
+    match (template.find("<title>"), template.find("</title>")) {
+        (Some(start), Some(end)) if start < end => format!(
+            "{}{head}{}",
+            &template[..start],
+            &template[end + "</title>".len()..]
+        ),
+        _ => template.replacen("</head>", &format!("{head}</head>"), 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{indexer::generate_index, test_support::TempContent};
+
+    const SHELL: &str = "<html><head><title>blogy</title></head><body></body></html>";
+
+    fn project() -> TempContent {
+        let project = TempContent::new();
+        project
+            .write("Dioxus.toml", "[application]\nout_dir = \"docs\"\n\n[web.app]\nbase_path = \"blog\"\n")
+            .write("docs/index.html", SHELL)
+            .write(
+                "content/pages/home.md",
+                "---\ndate: 2024-05-01\n---\n# Home\n\nWelcome to the *blog*.\n\n```rust\nfn main() {}\n```\n",
+            )
+            .write("content/pages/draft.md", "---\nsitemap: false\n---\nNot listed.\n");
+        let index = generate_index(&project.path().join("content"), "pages", None).unwrap();
+        project.write("content/index.json", &serde_json::to_string(&index).unwrap());
+        project
+    }
+
+    fn build(project: &TempContent, hash_router: bool) -> String {
+        run(BuildArgs {
+            content: project.path().join("content"),
+            site_url: "https://example.test/".to_string(),
+            project: project.path().to_path_buf(),
+            out_dir: None,
+            hash_router,
+        })
+        .unwrap();
+        std::fs::read_to_string(project.path().join("docs/sitemap.xml")).unwrap()
+    }
+
+    #[test]
+    fn pages_are_listed_and_baked_at_their_paths() {
+        let project = project();
+        let sitemap = build(&project, false);
+        assert!(sitemap.contains("<loc>https://example.test/blog/pages/home</loc>"), "{sitemap}");
+        assert!(sitemap.contains("<lastmod>2024-05-01</lastmod>"), "{sitemap}");
+        assert!(!sitemap.contains("draft"), "{sitemap}");
+
+        let robots = std::fs::read_to_string(project.path().join("docs/robots.txt")).unwrap();
+        assert!(robots.contains("Sitemap: https://example.test/blog/sitemap.xml"), "{robots}");
+
+        let page = std::fs::read_to_string(project.path().join("docs/pages/home/index.html")).unwrap();
+        assert!(page.contains(r#"<link rel="canonical" href="https://example.test/blog/pages/home">"#), "{page}");
+        assert!(page.contains(r#"content="Welcome to the blog.""#), "{page}");
+        assert!(!page.contains("<title>blogy</title>"), "{page}");
+    }
+
+    #[test]
+    fn hash_routing_lists_fragment_urls_and_bakes_nothing() {
+        let project = project();
+        let sitemap = build(&project, true);
+        assert!(sitemap.contains("<loc>https://example.test/blog/#/pages/home</loc>"), "{sitemap}");
+        assert!(!project.path().join("docs/pages").exists());
+    }
+}
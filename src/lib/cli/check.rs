@@ -0,0 +1,396 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    path::PathBuf,
+};
+
+use clap::Parser;
+use pulldown_cmark::{Event, Parser as MarkdownParser, Tag};
+
+use super::{content::ContentDir, CliError};
+use crate::{
+    utils::{
+        front_matter::split_front_matter,
+        json_db::{JsonDb, NavNode},
+        plugins::PageInfo,
+        render::{image_source, markdown_to_html, RenderContext, RenderOptions},
+        routing::{home_route, resolve_link},
+    },
+    Route, BASE_URL,
+};
+
+/// Renders every page of a content repository and reports links and images
+/// that lead nowhere.
+#[derive(Debug, Parser)]
+#[command(name = "blogy-check")]
+pub struct CheckArgs {
+    /// Local checkout of the content repository, the folder holding index.json
+    pub content: PathBuf,
+    /// Also request every external http(s) link and image
+    #[arg(long)]
+    pub external: bool,
+    /// Send the external requests to this origin instead, keeping path and
+    /// query, e.g. a local mock in CI
+    #[arg(long, value_name = "URL", requires = "external")]
+    pub external_mock: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Problem {
+    MissingFile(PathBuf),
+    DeadLink(String),
+    MissingAnchor(String),
+    MissingImage(String),
+    Unreachable,
+    External { url: String, error: String },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingFile(file) => write!(f, "{} does not exist", file.display()),
+            Self::DeadLink(dest) => write!(f, "link to {dest} leads to no page"),
+            Self::MissingAnchor(dest) => write!(f, "link to {dest}: no such anchor on that page"),
+            Self::MissingImage(dest) => write!(f, "image {dest} does not exist"),
+            Self::Unreachable => write!(f, "not in the menu and not linked from any page"),
+            Self::External { url, error } => write!(f, "{url}: {error}"),
+        }
+    }
+}
+
+/// What a page points at and which anchors it offers, once rendered.
+struct ScannedPage {
+    links: Vec<String>,
+    images: Vec<String>,
+    anchors: HashSet<String>,
+}
+
+/// Where a link leads when it does not lead nowhere.
+enum Target {
+    Page {
+        path: String,
+        anchor: Option<String>,
+    },
+    External(String),
+    /// `mailto:` and other schemes the site has no say over.
+    Other,
+}
+
+pub fn run(args: CheckArgs) -> Result<(), CliError> {
+    let content = ContentDir::open(&args.content)?;
+    let external = args.external.then_some(args.external_mock.as_deref());
+    let (pages, problems) = find_problems(&content, external)?;
+
+    for (page, problem) in &problems {
+        println!("{page}: {problem}");
+    }
+    if !problems.is_empty() {
+        return Err(CliError::Config(format!(
+            "found {} problems in {pages} pages",
+            problems.len()
+        )));
+    }
+    println!("Checked {pages} pages, no problems found");
+    Ok(())
+}
+
+/// Checks every page of `content`, requesting external URLs when `external`
+/// is set (to the mock origin it holds, if any). Returns how many pages were
+/// checked and the problems found, page by page in menu order.
+fn find_problems(
+    content: &ContentDir,
+    external: Option<Option<&str>>,
+) -> Result<(usize, Vec<(String, Problem)>), CliError> {
+    let mut db = content.load_db()?;
+    let render = RenderContext::new(BASE_URL, RenderOptions::default());
+
+    let paths = db.site_pages();
+    let mut problems: Vec<(String, Problem)> = Vec::new();
+    let mut pages = HashMap::new();
+    for path in &paths {
+        let Some(file) = content.page_file(&db, path) else {
+            continue;
+        };
+        match std::fs::read_to_string(&file) {
+            Ok(markdown) => {
+                // As the app does when the page is opened, so aliases count
+                let (front_matter, body) = split_front_matter(&markdown);
+                db.register_aliases(path, &front_matter);
+                let page = PageInfo {
+                    path,
+                    front_matter: &front_matter,
+                };
+                pages.insert(path.clone(), scan_page(body, &page, &render));
+            }
+            Err(_) => problems.push((path.clone(), Problem::MissingFile(file))),
+        }
+    }
+
+    let mut linked = HashSet::new();
+    let mut external_urls: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for path in &paths {
+        let Some(page) = pages.get(path) else {
+            continue;
+        };
+        for dest in &page.links {
+            match link_target(&db, path, dest) {
+                Ok(Target::Page {
+                    path: target,
+                    anchor,
+                }) => {
+                    if let (Some(anchor), Some(page)) = (&anchor, pages.get(&target)) {
+                        if !page.anchors.contains(anchor) {
+                            problems.push((path.clone(), Problem::MissingAnchor(dest.clone())));
+                        }
+                    }
+                    linked.insert(target);
+                }
+                Ok(Target::External(url)) => {
+                    external_urls.entry(url).or_default().push(path.clone())
+                }
+                Ok(Target::Other) => {}
+                Err(problem) => problems.push((path.clone(), problem)),
+            }
+        }
+        for dest in &page.images {
+            match image_source(dest, path) {
+                Some(source) if !content.file(&source).is_file() => {
+                    problems.push((path.clone(), Problem::MissingImage(dest.clone())));
+                }
+                Some(_) => {}
+                None => external_urls.entry(dest.clone()).or_default().push(path.clone()),
+            }
+        }
+    }
+
+    let Route::PageContent { path: home } = home_route();
+    let mut reachable = menu_pages(&db, db.nav_tree());
+    reachable.extend(linked);
+    reachable.extend(db.find_page(&home.join("/")).map(str::to_string));
+    for path in &paths {
+        if !reachable.contains(path) {
+            problems.push((path.clone(), Problem::Unreachable));
+        }
+    }
+
+    if let Some(mock) = external {
+        for (url, error) in check_external(&external_urls, mock)? {
+            for page in &external_urls[&url] {
+                let (url, error) = (url.clone(), error.clone());
+                problems.push((page.clone(), Problem::External { url, error }));
+            }
+        }
+    }
+
+    // Report page by page in menu order
+    problems.sort_by_key(|(page, _)| paths.iter().position(|path| path == page));
+    Ok((paths.len(), problems))
+}
+
+fn scan_page(body: &str, page: &PageInfo, render: &RenderContext) -> ScannedPage {
+    let mut links = Vec::new();
+    // The page's social image counts too
+    let mut images: Vec<String> = page
+        .front_matter
+        .get_str("image")
+        .map(str::to_string)
+        .into_iter()
+        .collect();
+    for event in MarkdownParser::new_ext(body, render.options().markdown) {
+        match event {
+            Event::Start(Tag::Link { dest_url, .. }) => links.push(dest_url.to_string()),
+            Event::Start(Tag::Image { dest_url, .. }) => images.push(dest_url.to_string()),
+            _ => {}
+        }
+    }
+
+    // Anchors are whatever ids the rendered page ends up with
+    let html = markdown_to_html(body, page, render).html;
+    ScannedPage {
+        links,
+        images,
+        anchors: anchors(&html),
+    }
+}
+
+/// Resolves `dest`, linked from the page at `path`, the way the rendered page
+/// does, following redirects to the page it ends up on.
+fn link_target(db: &JsonDb, path: &str, dest: &str) -> Result<Target, Problem> {
+    if dest.starts_with("http://") || dest.starts_with("https://") {
+        return Ok(Target::External(dest.to_string()));
+    }
+
+    let (route, anchor) = match dest.strip_prefix('#') {
+        Some(anchor) if !anchor.starts_with('/') => (path.to_string(), Some(anchor.to_string())),
+        _ => match resolve_link(dest, path) {
+            Some(route) => match route.split_once('#') {
+                Some((route, anchor)) => (route.to_string(), Some(anchor.to_string())),
+                None => (route, None),
+            },
+            None => return Ok(Target::Other),
+        },
+    };
+
+    let target = db
+        .find_page(&route)
+        .map(str::to_string)
+        .or_else(|| {
+            db.redirect_for(&route)
+                .and_then(|target| db.find_page(&target).map(str::to_string))
+        })
+        .ok_or_else(|| Problem::DeadLink(dest.to_string()))?;
+    Ok(Target::Page {
+        path: target,
+        anchor: anchor.filter(|anchor| !anchor.is_empty()),
+    })
+}
+
+/// Pages reachable through the visible menus.
+fn menu_pages(db: &JsonDb, nodes: &[NavNode]) -> HashSet<String> {
+    let mut pages = HashSet::new();
+    for node in nodes {
+        if let Some(page) = db.find_page(node.path()) {
+            pages.insert(page.to_string());
+        }
+        if let NavNode::Directory { children, .. } = node {
+            pages.extend(menu_pages(db, children));
+        }
+    }
+    pages
+}
+
+/// Values of the `id` and `name` attributes in rendered html.
+fn anchors(html: &str) -> HashSet<String> {
+    let mut anchors = HashSet::new();
+    for attribute in [" id=\"", " name=\""] {
+        for (start, _) in html.match_indices(attribute) {
+            let value = &html[start + attribute.len()..];
+            if let Some((value, _)) = value.split_once('"') {
+                anchors.insert(value.to_string());
+            }
+        }
+    }
+    anchors
+}
+
+/// Requests every external URL, returning the ones that failed with why.
+fn check_external(
+    urls: &BTreeMap<String, Vec<String>>,
+    mock: Option<&str>,
+) -> Result<Vec<(String, String)>, CliError> {
+    let mock = mock
+        .map(reqwest::Url::parse)
+        .transpose()
+        .map_err(|e| CliError::Config(format!("invalid --external-mock: {e}")))?;
+    let runtime = tokio::runtime::Runtime::new()
+        .map_err(|e| CliError::Config(format!("starting the async runtime: {e}")))?;
+
+    runtime.block_on(async {
+        let client = reqwest::Client::new();
+        let mut requests = tokio::task::JoinSet::new();
+        for url in urls.keys() {
+            let (client, url, mock) = (client.clone(), url.clone(), mock.clone());
+            requests.spawn(async move {
+                let error = match request_url(&url, mock) {
+                    Ok(target) => match client.get(target).send().await {
+                        Ok(response) if response.status().is_success() => return None,
+                        Ok(response) => response.status().to_string(),
+                        Err(e) => e.to_string(),
+                    },
+                    Err(e) => e,
+                };
+                Some((url, error))
+            });
+        }
+
+        let mut failed = Vec::new();
+        while let Some(result) = requests.join_next().await {
+            if let Ok(Some(failure)) = result {
+                failed.push(failure);
+            }
+        }
+        Ok(failed)
+    })
+}
+
+/// `url`, or the same path and query on the mock origin.
+fn request_url(url: &str, mock: Option<reqwest::Url>) -> Result<reqwest::Url, String> {
+    let url = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+    let Some(mut target) = mock else {
+        return Ok(url);
+    };
+    target.set_path(url.path());
+    target.set_query(url.query());
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::test_support::TempContent;
+
+    const INDEX: &str = r#"{
+        "root": { "type": "directory", "name": "Pages", "path": "/pages", "children": [
+            { "type": "page", "name": "Home", "path": "/pages/home", "file": "home.md" },
+            { "type": "page", "name": "Guide", "path": "/pages/guide", "file": "guide.md" },
+            { "type": "page", "name": "Gone", "path": "/pages/gone", "file": "gone.md" },
+            { "type": "page", "name": "Orphan", "path": "/pages/orphan", "file": "orphan.md", "hidden": true }
+        ]},
+        "redirects": { "/pages/old-guide": "/pages/guide" }
+    }"#;
+
+    fn check(content: &TempContent) -> Vec<(String, Problem)> {
+        let dir = ContentDir::open(content.path()).unwrap();
+        let (pages, problems) = find_problems(&dir, None).unwrap();
+        assert_eq!(pages, 4);
+        problems
+    }
+
+    #[test]
+    fn planted_problems_are_reported_by_page() {
+        let content = TempContent::new();
+        content
+            .write("index.json", INDEX)
+            .write(
+                "pages/home.md",
+                "# Home\n\n[Guide](guide#setup) [Moved](old-guide) [Gone](gone) \
+                 [Nowhere](/pages/nowhere) [Mail](mailto:me@example.test)\n\n\
+                 ![Logo](logo.png) ![Photo](photo.png)\n",
+            )
+            .write("pages/home/logo.png", "")
+            .write("pages/guide.md", "# Guide\n\n<a id=\"setup\"></a>\n\n[Back](home#top)\n")
+            .write("pages/orphan.md", "# Orphan\n");
+
+        let problems = check(&content);
+        let expected = [
+            ("/pages/home", Problem::DeadLink("/pages/nowhere".into())),
+            ("/pages/home", Problem::MissingImage("photo.png".into())),
+            ("/pages/guide", Problem::MissingAnchor("home#top".into())),
+            ("/pages/gone", Problem::MissingFile(content.path().join("pages/gone.md"))),
+            ("/pages/orphan", Problem::Unreachable),
+        ];
+        assert_eq!(problems.len(), expected.len(), "{problems:?}");
+        for (page, problem) in expected {
+            assert!(
+                problems.contains(&(page.to_string(), problem.clone())),
+                "{page}: {problem} not in {problems:?}"
+            );
+        }
+        // Reported in menu order
+        let menu = ["/pages/home", "/pages/guide", "/pages/gone", "/pages/orphan"];
+        assert!(problems.is_sorted_by_key(|(page, _)| menu.iter().position(|path| path == page)));
+    }
+
+    #[test]
+    fn linked_hidden_pages_are_reachable() {
+        let content = TempContent::new();
+        content
+            .write("index.json", INDEX)
+            .write("pages/home.md", "# Home\n\n[Orphan](orphan) [Gone](gone)\n")
+            .write("pages/guide.md", "# Guide\n")
+            .write("pages/gone.md", "# Gone\n")
+            .write("pages/orphan.md", "# Orphan\n");
+
+        assert_eq!(check(&content), []);
+    }
+}
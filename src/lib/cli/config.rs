@@ -0,0 +1,74 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::CliError;
+
+/// Where `dx` writes the bundled app when `Dioxus.toml` does not say.
+const DEFAULT_OUT_DIR: &str = "dist";
+
+/// The parts of `Dioxus.toml` the native tools need.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectConfig {
+    /// The bundled app, relative paths resolved against the project.
+    pub out_dir: PathBuf,
+    /// The path the app is served under, without slashes; may be empty.
+    pub base_path: String,
+}
+
+#[derive(Default, Deserialize)]
+struct DioxusToml {
+    #[serde(default)]
+    application: ApplicationSection,
+    #[serde(default)]
+    web: WebSection,
+}
+
+#[derive(Default, Deserialize)]
+struct ApplicationSection {
+    out_dir: Option<PathBuf>,
+}
+
+#[derive(Default, Deserialize)]
+struct WebSection {
+    #[serde(default)]
+    app: WebAppSection,
+}
+
+#[derive(Default, Deserialize)]
+struct WebAppSection {
+    base_path: Option<String>,
+}
+
+impl ProjectConfig {
+    /// Reads `Dioxus.toml` from `project_dir`.
+    pub fn load(project_dir: &Path) -> Result<Self, CliError> {
+        let path = project_dir.join("Dioxus.toml");
+        let text = std::fs::read_to_string(&path).map_err(CliError::io(&path))?;
+        let config: DioxusToml = toml::from_str(&text)
+            .map_err(|e| CliError::Config(format!("{}: {e}", path.display())))?;
+
+        let out_dir = config
+            .application
+            .out_dir
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_OUT_DIR));
+        Ok(Self {
+            out_dir: project_dir.join(out_dir),
+            base_path: config
+                .web
+                .app
+                .base_path
+                .map(|path| path.trim_matches('/').to_string())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// The base path as a `/prefix`, or empty.
+    pub fn base_prefix(&self) -> String {
+        if self.base_path.is_empty() {
+            String::new()
+        } else {
+            format!("/{}", self.base_path)
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use super::CliError;
+use crate::utils::{index::parse_index, json_db::JsonDb};
+
+/// Revision recorded for databases built from a local checkout.
+const LOCAL_REVISION: &str = "local";
+
+/// A local checkout of the content repository: `index.json` next to the
+/// markdown files, laid out as they are served from `BASE_URL`.
+#[derive(Debug, Clone)]
+pub struct ContentDir {
+    root: PathBuf,
+}
+
+impl ContentDir {
+    pub fn open(root: &Path) -> Result<Self, CliError> {
+        let index = root.join("index.json");
+        if !index.is_file() {
+            return Err(CliError::Config(format!(
+                "{} is not a content folder: index.json is missing",
+                root.display()
+            )));
+        }
+        Ok(Self {
+            root: root.to_path_buf(),
+        })
+    }
+
+    pub fn load_db(&self) -> Result<JsonDb, CliError> {
+        let path = self.root.join("index.json");
+        let json = std::fs::read_to_string(&path).map_err(CliError::io(&path))?;
+        let index = parse_index(&json)?;
+        Ok(JsonDb::from_index(&index, LOCAL_REVISION))
+    }
+
+    /// The markdown file behind the page served at `path`.
+    pub fn page_file(&self, db: &JsonDb, path: &str) -> Option<PathBuf> {
+        db.page_file(path).map(|file| self.file(&file))
+    }
+
+    /// A file given relative to the content root, as it is fetched below
+    /// `BASE_URL`.
+    pub fn file(&self, relative: &str) -> PathBuf {
+        self.root.join(relative.trim_start_matches('/'))
+    }
+
+    /// Committer date of the last commit touching `file`, when the content
+    /// folder is a git checkout.
+    pub fn last_commit_date(&self, file: &Path) -> Option<String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.root)
+            .args(["log", "-1", "--format=%cI", "--"])
+            .arg(file)
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let date = String::from_utf8(output.stdout).ok()?;
+        let date = date.trim();
+        (!date.is_empty()).then(|| date.to_string())
+    }
+}
@@ -0,0 +1,316 @@
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+use pulldown_cmark::{Event, Parser as MarkdownParser, Tag, TagEnd};
+
+use super::{write_file, CliError};
+use crate::utils::{
+    front_matter::{split_front_matter, FrontMatter},
+    index::{parse_index, IndexFile, IndexNode, Layout, INDEX_VERSION},
+    json_db::{DataError, JsonDb},
+};
+
+/// File stems that make a page the landing page of its folder, besides the
+/// folder's own name.
+const LANDING_STEMS: [&str; 2] = ["index", "readme"];
+
+/// Writes `index.json` for a folder of markdown files.
+#[derive(Debug, Parser)]
+#[command(name = "blogy-index")]
+pub struct IndexArgs {
+    /// Content repository to index; index.json is written at its top
+    pub content: PathBuf,
+    /// Folder below the content root that becomes the top of the menu
+    #[arg(long, default_value = "pages")]
+    pub root: String,
+    /// Write somewhere other than <content>/index.json
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+    /// Fail instead of writing when index.json is out of date
+    #[arg(long)]
+    pub check: bool,
+}
+
+/// What a markdown file says about itself.
+struct PageInfo {
+    title: String,
+    front_matter: FrontMatter,
+}
+
+pub fn run(args: IndexArgs) -> Result<(), CliError> {
+    let output = args
+        .output
+        .unwrap_or_else(|| args.content.join("index.json"));
+    let existing = std::fs::read_to_string(&output).ok();
+    let previous = existing.as_deref().and_then(|json| parse_index(json).ok());
+    let index = generate_index(&args.content, &args.root, previous)?;
+
+    let json = serde_json::to_string_pretty(&index).map_err(DataError::from)? + "\n";
+    let pages = JsonDb::from_index(&index, "local").site_pages().len();
+
+    if args.check {
+        if existing.as_deref() != Some(json.as_str()) {
+            return Err(CliError::Config(format!(
+                "{} is out of date, run blogy-index to regenerate it",
+                output.display()
+            )));
+        }
+        println!("{} is up to date ({pages} pages)", output.display());
+        return Ok(());
+    }
+
+    write_file(&output, &json)?;
+    println!("Indexed {pages} pages into {}", output.display());
+    Ok(())
+}
+
+/// Indexes the `root` folder of `content`. Site settings and redirects are
+/// maintained by hand, so they are carried over from `previous`.
+pub fn generate_index(
+    content: &Path,
+    root: &str,
+    previous: Option<IndexFile>,
+) -> Result<IndexFile, CliError> {
+    let root_name = root.trim_matches('/');
+    let root = index_directory(&content.join(root_name), &format!("/{root_name}"))?;
+    let (site, redirects) = previous
+        .map(|index| (index.site, index.redirects))
+        .unwrap_or_default();
+
+    Ok(IndexFile {
+        version: INDEX_VERSION,
+        root,
+        site,
+        redirects,
+    })
+}
+
+/// Builds the directory node for `dir`, served at `path`. Children are
+/// listed by file name so the output only changes when the content does;
+/// menu order is left to `weight`.
+fn index_directory(dir: &Path, path: &str) -> Result<IndexNode, CliError> {
+    let dir_name = file_name(dir);
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(CliError::io(dir))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|entry| !file_name(entry).starts_with('.'))
+        .collect();
+    entries.sort_by_key(|entry| file_name(entry).to_lowercase());
+
+    let mut children = Vec::new();
+    let mut landing = None;
+    for entry in entries {
+        let name = file_name(&entry);
+        if entry.is_dir() {
+            children.push(index_directory(&entry, &format!("{path}/{name}"))?);
+            continue;
+        }
+        if entry.extension().and_then(|ext| ext.to_str()) != Some("md") {
+            continue;
+        }
+
+        let stem = name.trim_end_matches(".md").to_string();
+        let info = read_page(&entry, &stem)?;
+        let is_landing = stem.eq_ignore_ascii_case(&dir_name)
+            || LANDING_STEMS.contains(&stem.to_lowercase().as_str());
+        if is_landing && landing.is_none() {
+            landing = Some((name, info));
+            continue;
+        }
+
+        let fm = &info.front_matter;
+        children.push(IndexNode::Page {
+            name: stem.clone(),
+            path: page_path(path, &stem),
+            file: Some(name),
+            aliases: fm.aliases(),
+            weight: fm.get_i32("weight"),
+            hidden: fm.get_bool("hidden").unwrap_or(false),
+            nav_title: nav_title(&info, &stem),
+        });
+    }
+
+    // A landing page is served at the folder's own path and describes the
+    // section in the menu
+    let (mut weight, mut hidden, mut section_title, mut layout) = (None, false, None, None);
+    if let Some((file, info)) = landing {
+        let fm = &info.front_matter;
+        weight = fm.get_i32("weight");
+        hidden = fm.get_bool("hidden").unwrap_or(false);
+        section_title = nav_title(&info, &dir_name);
+        layout = match fm.get_str("layout") {
+            Some("docs") => Some(Layout::Docs),
+            Some("blog") => Some(Layout::Blog),
+            _ => None,
+        };
+        let landing_path = match path.rsplit_once('/') {
+            Some((parent, _)) => page_path(parent, &dir_name),
+            None => path.to_lowercase(),
+        };
+        children.insert(
+            0,
+            IndexNode::Page {
+                name: dir_name.clone(),
+                path: landing_path,
+                file: Some(file),
+                aliases: fm.aliases(),
+                weight: None,
+                hidden: false,
+                nav_title: None,
+            },
+        );
+    }
+
+    Ok(IndexNode::Directory {
+        name: dir_name,
+        path: path.to_string(),
+        children: children.into(),
+        layout,
+        weight,
+        hidden,
+        nav_title: section_title,
+    })
+}
+
+/// Route of the page `name` in the folder served at `dir_path`. `JsonDb`
+/// keeps a page path as is only when it ends in the lowercased name, while
+/// the folder keeps its own case so the file can still be fetched.
+fn page_path(dir_path: &str, name: &str) -> String {
+    format!("{dir_path}/{}", name.to_lowercase())
+}
+
+fn read_page(file: &Path, stem: &str) -> Result<PageInfo, CliError> {
+    let markdown = std::fs::read_to_string(file).map_err(CliError::io(file))?;
+    let (front_matter, body) = split_front_matter(&markdown);
+    let title = front_matter
+        .title()
+        .map(str::to_string)
+        .or_else(|| first_heading(body))
+        .unwrap_or_else(|| stem.to_string());
+    Ok(PageInfo {
+        title,
+        front_matter,
+    })
+}
+
+/// The menu label, when it differs from the name the path is built from.
+fn nav_title(info: &PageInfo, name: &str) -> Option<String> {
+    let label = info.front_matter.get_str("nav_title").unwrap_or(&info.title);
+    (label != name).then(|| label.to_string())
+}
+
+fn first_heading(markdown: &str) -> Option<String> {
+    let mut heading: Option<String> = None;
+    for event in MarkdownParser::new(markdown) {
+        match event {
+            Event::Start(Tag::Heading { .. }) => heading = Some(String::new()),
+            Event::End(TagEnd::Heading(_)) => {
+                let text = heading.take().unwrap_or_default();
+                if !text.trim().is_empty() {
+                    return Some(text.trim().to_string());
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(heading) = &mut heading {
+                    heading.push_str(&text);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::test_support::TempContent;
+
+    #[test]
+    fn mixed_case_pages_resolve_to_their_files() {
+        let content = TempContent::new();
+        content
+            .write("pages/About.md", "# About us\n")
+            .write("pages/Guide/Guide.md", "---\nweight: 1\n---\n# The guide\n")
+            .write("pages/Guide/Intro.md", "# Intro\n")
+            .write("pages/Guide/Deep Dive/README.md", "# Deep\n")
+            .write("pages/Guide/Deep Dive/Setup-Steps.md", "# Setup\n")
+            .write("pages/home.md", "# Home\n");
+
+        let index = generate_index(content.path(), "pages", None).unwrap();
+        let db = JsonDb::from_index(&index, "test");
+
+        let pages = db.site_pages();
+        assert_eq!(pages.len(), 6, "{pages:?}");
+        for page in &pages {
+            let file = db.page_file(page).unwrap();
+            let file = content.path().join(file.trim_start_matches('/'));
+            assert!(file.is_file(), "{page} is served from missing {}", file.display());
+        }
+
+        for route in [
+            "/pages/about",
+            "/pages/guide",
+            "/pages/guide/intro",
+            "/pages/guide/deep dive",
+            "/pages/guide/deep dive/setup-steps",
+            "/pages/home",
+        ] {
+            assert!(db.find_page(route).is_some(), "no page at {route}");
+        }
+        assert_eq!(db.page_file("/pages/guide/intro").as_deref(), Some("/pages/Guide/Intro.md"));
+        assert_eq!(db.page_file("/pages/guide").as_deref(), Some("/pages/Guide/Guide.md"));
+    }
+
+    #[test]
+    fn titles_come_from_front_matter_or_first_heading() {
+        let content = TempContent::new();
+        content
+            .write("pages/a.md", "---\ntitle: From front matter\n---\n# Heading\n")
+            .write("pages/b.md", "Text first\n\n## Second `level`\n")
+            .write("pages/c.md", "No heading at all\n");
+
+        let index = generate_index(content.path(), "pages", None).unwrap();
+        let IndexNode::Directory { children, .. } = index.root else {
+            panic!("root is not a directory");
+        };
+        let titles: Vec<Option<String>> = children
+            .iter()
+            .map(|child| match child {
+                IndexNode::Page { nav_title, .. } => nav_title.clone(),
+                _ => panic!("unexpected node {child:?}"),
+            })
+            .collect();
+        assert_eq!(
+            titles,
+            [Some("From front matter".to_string()), Some("Second level".to_string()), None]
+        );
+    }
+
+    #[test]
+    fn output_is_stable_and_keeps_redirects() {
+        let content = TempContent::new();
+        content
+            .write("pages/b.md", "# B\n")
+            .write("pages/A.md", "---\naliases: [/old/a]\n---\n# A\n");
+        let mut previous = generate_index(content.path(), "pages", None).unwrap();
+        previous
+            .redirects
+            .insert("/gone".to_string(), "/pages/b".to_string());
+
+        let first = generate_index(content.path(), "pages", Some(previous.clone())).unwrap();
+        let second = generate_index(content.path(), "pages", Some(first.clone())).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.redirects, previous.redirects);
+
+        let db = JsonDb::from_index(&first, "test");
+        assert_eq!(db.redirect_for("/old/a").as_deref(), Some("/pages/a"));
+        assert_eq!(db.redirect_for("/gone").as_deref(), Some("/pages/b"));
+    }
+}
@@ -0,0 +1,112 @@
+//! Native tools for working on a content repository outside the browser,
+//! built with the `cli` feature. The binaries in `src/bin` only parse their
+//! arguments and call into here.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use crate::utils::json_db::DataError;
+
+pub mod build;
+pub mod check;
+mod config;
+mod content;
+pub mod indexer;
+pub mod serve;
+mod sitemap;
+
+#[derive(Debug)]
+pub enum CliError {
+    Io { path: PathBuf, error: std::io::Error },
+    Data(DataError),
+    Config(String),
+    Server(std::io::Error),
+}
+
+impl CliError {
+    fn io(path: &Path) -> impl FnOnce(std::io::Error) -> Self + '_ {
+        move |error| Self::Io {
+            path: path.to_path_buf(),
+            error,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "{}: {error}", path.display()),
+            Self::Data(e) => write!(f, "{e}"),
+            Self::Config(message) => write!(f, "{message}"),
+            Self::Server(e) => write!(f, "dev server: {e}"),
+        }
+    }
+}
+
+impl From<DataError> for CliError {
+    fn from(e: DataError) -> Self {
+        Self::Data(e)
+    }
+}
+
+/// Prints why a command failed and turns its result into the exit code.
+pub fn report(result: Result<(), CliError>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), CliError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(CliError::io(parent))?;
+    }
+    std::fs::write(path, contents).map_err(CliError::io(path))
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+    /// A content folder in the temp dir, removed again when dropped.
+    pub struct TempContent {
+        root: PathBuf,
+    }
+
+    impl TempContent {
+        pub fn new() -> Self {
+            let root = std::env::temp_dir().join(format!(
+                "blogy-test-{}-{}",
+                std::process::id(),
+                NEXT.fetch_add(1, Ordering::Relaxed)
+            ));
+            std::fs::create_dir_all(&root).unwrap();
+            Self { root }
+        }
+
+        /// Writes `contents` to `relative`, creating its folders.
+        pub fn write(&self, relative: &str, contents: &str) -> &Self {
+            super::write_file(&self.root.join(relative), contents).unwrap();
+            self
+        }
+
+        pub fn path(&self) -> &Path {
+            &self.root
+        }
+    }
+
+    impl Drop for TempContent {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.root);
+        }
+    }
+}
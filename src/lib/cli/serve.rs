@@ -0,0 +1,332 @@
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::State,
+    http::{header, HeaderValue, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::get,
+    Json, Router,
+};
+use clap::Parser;
+use notify::{event::ModifyKind, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+use tower_http::{cors::CorsLayer, services::ServeDir, set_header::SetResponseHeaderLayer};
+
+use super::{indexer::generate_index, CliError};
+use crate::utils::index::parse_index;
+
+/// Quiet period after a file event before the change is announced, so the
+/// several events of one save are reported once.
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Serves a content folder to an app built with `BLOGY_DEV_SERVER`, with
+/// `index.json` generated on every request and a change event per save.
+#[derive(Debug, Parser)]
+#[command(name = "blogy-serve")]
+pub struct ServeArgs {
+    /// Content repository to serve
+    pub content: PathBuf,
+    /// Folder below the content root that becomes the top of the menu
+    #[arg(long, default_value = "pages")]
+    pub root: String,
+    /// Port to listen on
+    #[arg(long, default_value_t = 8787)]
+    pub port: u16,
+}
+
+#[derive(Clone)]
+struct DevServer {
+    content: Arc<PathBuf>,
+    root: Arc<str>,
+    /// Keeps revisions of earlier runs from matching what the app cached.
+    started: u64,
+    changes: Arc<AtomicU64>,
+    events: broadcast::Sender<String>,
+}
+
+impl DevServer {
+    fn new(content: &Path, root: &str) -> Self {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        Self {
+            content: Arc::new(content.to_path_buf()),
+            root: root.into(),
+            started,
+            changes: Arc::new(AtomicU64::new(0)),
+            events: broadcast::channel(16).0,
+        }
+    }
+
+    /// Stands in for the commit hash the app checks against GitHub.
+    fn revision(&self) -> String {
+        format!(
+            "dev-{}-{}",
+            self.started,
+            self.changes.load(Ordering::Relaxed)
+        )
+    }
+}
+
+pub fn run(args: ServeArgs) -> Result<(), CliError> {
+    if !args.content.is_dir() {
+        return Err(CliError::Config(format!(
+            "{} is not a folder",
+            args.content.display()
+        )));
+    }
+    tokio::runtime::Runtime::new()
+        .map_err(CliError::Server)?
+        .block_on(serve(args))
+}
+
+async fn serve(args: ServeArgs) -> Result<(), CliError> {
+    let server = DevServer::new(&args.content, &args.root);
+    // Watching stops when the watcher is dropped
+    let _watcher = watch(&server)?;
+
+    let address = SocketAddr::from(([127, 0, 0, 1], args.port));
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .map_err(CliError::Server)?;
+    println!("Serving {} at http://{address}", args.content.display());
+    println!("Run the app with BLOGY_DEV_SERVER=http://{address} dx serve");
+    axum::serve(listener, app(server)).await.map_err(CliError::Server)
+}
+
+fn app(server: DevServer) -> Router {
+    let files = ServeDir::new(server.content.as_path());
+    Router::new()
+        .route("/index.json", get(index_json))
+        .route("/revision", get(revision))
+        .route("/events", get(events))
+        .fallback_service(files)
+        // Edited files must not come from the browser cache
+        .layer(SetResponseHeaderLayer::overriding(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("no-store"),
+        ))
+        // The app is served by `dx serve` from another port
+        .layer(CorsLayer::permissive())
+        .with_state(server)
+}
+
+/// Bumps the revision and tells connected apps whenever a file below the
+/// content folder is created, written or removed.
+fn watch(server: &DevServer) -> Result<RecommendedWatcher, CliError> {
+    let (changed, mut received) = mpsc::unbounded_channel();
+    let content = server.content.clone();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        let edits_content = match event.kind {
+            EventKind::Modify(ModifyKind::Metadata(_)) => false,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_) => true,
+            _ => false,
+        };
+        if edits_content && event.paths.iter().any(|path| !is_hidden(&content, path)) {
+            let _ = changed.send(());
+        }
+    })
+    .map_err(|e| CliError::Config(format!("watching {}: {e}", server.content.display())))?;
+    watcher
+        .watch(&server.content, RecursiveMode::Recursive)
+        .map_err(|e| CliError::Config(format!("watching {}: {e}", server.content.display())))?;
+
+    let server = server.clone();
+    tokio::spawn(async move {
+        while received.recv().await.is_some() {
+            while let Ok(Some(())) = tokio::time::timeout(DEBOUNCE, received.recv()).await {}
+            server.changes.fetch_add(1, Ordering::Relaxed);
+            let revision = server.revision();
+            println!("Content changed, now at {revision}");
+            let _ = server.events.send(revision);
+        }
+    });
+    Ok(watcher)
+}
+
+/// Files inside dot folders such as `.git` are not content.
+fn is_hidden(content: &Path, path: &Path) -> bool {
+    let relative = path.strip_prefix(content).unwrap_or(path);
+    relative.components().any(|part| match part {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => false,
+    })
+}
+
+async fn index_json(State(server): State<DevServer>) -> Response {
+    let index = tokio::task::spawn_blocking(move || {
+        let existing = std::fs::read_to_string(server.content.join("index.json")).ok();
+        let previous = existing.as_deref().and_then(|json| parse_index(json).ok());
+        generate_index(&server.content, &server.root, previous)
+    })
+    .await;
+
+    match index {
+        Ok(Ok(index)) => Json(index).into_response(),
+        Ok(Err(e)) => {
+            eprintln!("error: {e}");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn revision(State(server): State<DevServer>) -> String {
+    server.revision()
+}
+
+/// One message carrying the new revision per change. Apps that fall behind
+/// simply get the next one.
+async fn events(
+    State(server): State<DevServer>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = BroadcastStream::new(server.events.subscribe())
+        .filter_map(|revision| revision.ok())
+        .map(|revision| Ok(Event::default().data(revision)));
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cli::test_support::TempContent,
+        utils::{
+            backend::{MemoryStorage, ReqwestClient},
+            json_db::{ContentLoader, ContentSource, JsonDb},
+            render::RenderContext,
+            syntax::Syntaxes,
+        },
+    };
+
+    /// Serves `content` on a free port, watching it as `run` does.
+    async fn start(content: &TempContent) -> (String, RecommendedWatcher) {
+        let server = DevServer::new(content.path(), "pages");
+        let watcher = watch(&server).unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app(server)).await });
+        (url, watcher)
+    }
+
+    #[tokio::test]
+    async fn serves_a_fresh_index_and_the_files_uncached() {
+        let content = TempContent::new();
+        content.write("pages/home.md", "# Home\n");
+        let (url, _watcher) = start(&content).await;
+        let client = reqwest::Client::new();
+
+        let index = client.get(format!("{url}/index.json")).send().await.unwrap();
+        assert!(index.status().is_success());
+        let index = parse_index(&index.text().await.unwrap()).unwrap();
+        assert!(JsonDb::from_index(&index, "dev").find_page("/pages/home").is_some());
+
+        let page = client
+            .get(format!("{url}/pages/home.md"))
+            .header(header::ORIGIN, "http://localhost:8080")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(page.headers()[header::CACHE_CONTROL], "no-store");
+        assert!(page.headers().contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+        assert_eq!(page.text().await.unwrap(), "# Home\n");
+
+        let missing = client.get(format!("{url}/pages/nope.md")).send().await.unwrap();
+        assert_eq!(missing.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn edits_bump_the_revision_but_dot_folders_do_not() {
+        let content = TempContent::new();
+        content.write("pages/home.md", "# Home\n").write(".git/HEAD", "ref\n");
+        let (url, _watcher) = start(&content).await;
+        let revision = || async {
+            reqwest::get(format!("{url}/revision")).await.unwrap().text().await.unwrap()
+        };
+
+        let before = revision().await;
+        assert!(before.starts_with("dev-") && before.ends_with("-0"), "{before}");
+
+        content.write(".git/HEAD", "ref: main\n");
+        tokio::time::sleep(DEBOUNCE * 4).await;
+        assert_eq!(revision().await, before);
+
+        content.write("pages/home.md", "# Home again\n");
+        let mut after = before.clone();
+        for _ in 0..50 {
+            tokio::time::sleep(DEBOUNCE).await;
+            after = revision().await;
+            if after != before {
+                break;
+            }
+        }
+        assert!(after.ends_with("-1"), "{before} -> {after}");
+    }
+
+    #[tokio::test]
+    async fn the_app_loader_follows_the_dev_server() {
+        let content = TempContent::new();
+        content
+            .write("pages/home.md", "# Home\n\n```toy\nfn\n```\n")
+            .write(
+                "syntax/toy.sublime-syntax",
+                "%YAML 1.2\n---\nname: Toy\nscope: source.toy\nfile_extensions: [toy]\ncontexts:\n  main:\n    - match: '\\bfn\\b'\n      scope: keyword.toy\n",
+            );
+        let (url, _watcher) = start(&content).await;
+        let loader = ContentLoader::new(
+            ReqwestClient::default(),
+            MemoryStorage::default(),
+            ContentSource::DevServer { url: url.clone() },
+        );
+        let ctx = RenderContext::new(&url, Default::default())
+            .with_syntaxes(Syntaxes::new(Some(format!("{url}/syntax"))));
+
+        let db = loader.load().await.unwrap();
+        let source = db.page_source("/pages/home").unwrap();
+        let page = loader.render(&source, &ctx).await.unwrap();
+        assert!(page.html.contains("Home</h1>"), "{}", page.html);
+        assert!(ctx.syntaxes().read().find_syntax_by_token("toy").is_some());
+        assert!(loader.check_revision().await.unwrap().is_none());
+
+        content.write("pages/home.md", "# Home again\n");
+        let mut pending = None;
+        for _ in 0..50 {
+            tokio::time::sleep(DEBOUNCE).await;
+            pending = loader.check_revision().await.unwrap();
+            if pending.is_some() {
+                break;
+            }
+        }
+        let db = loader.apply(pending.expect("the edit bumps the revision"));
+        let source = db.page_source("/pages/home").unwrap();
+        let page = loader.render(&source, &ctx).await.unwrap();
+        assert!(page.html.contains("Home again</h1>"), "{}", page.html);
+    }
+
+    #[test]
+    fn dot_folders_are_hidden() {
+        let content = Path::new("/content");
+        assert!(is_hidden(content, Path::new("/content/.git/HEAD")));
+        assert!(is_hidden(content, Path::new("/content/pages/.draft.md")));
+        assert!(!is_hidden(content, Path::new("/content/pages/home.md")));
+        // Only what lies below the content folder counts
+        assert!(!is_hidden(Path::new("/home/me/.sites/blog"), Path::new("/home/me/.sites/blog/pages/home.md")));
+    }
+}
@@ -0,0 +1,58 @@
+use crate::utils::front_matter::FrontMatter;
+
+/// One page listed in `sitemap.xml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapEntry {
+    pub url: String,
+    /// W3C date or date-time of the last change.
+    pub lastmod: Option<String>,
+}
+
+pub fn sitemap_xml(entries: &[SitemapEntry]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for entry in entries {
+        xml.push_str("  <url>\n");
+        xml.push_str(&format!("    <loc>{}</loc>\n", xml_escape(&entry.url)));
+        if let Some(lastmod) = &entry.lastmod {
+            xml.push_str(&format!("    <lastmod>{}</lastmod>\n", xml_escape(lastmod)));
+        }
+        xml.push_str("  </url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+pub fn robots_txt(sitemap_url: &str) -> String {
+    format!("User-agent: *\nAllow: /\n\nSitemap: {sitemap_url}\n")
+}
+
+/// The page's own `lastmod:`, `updated:` or `date:`, when it starts with a
+/// `YYYY-MM-DD` date.
+pub fn front_matter_lastmod(front_matter: &FrontMatter) -> Option<String> {
+    ["lastmod", "updated", "date"]
+        .into_iter()
+        .filter_map(|key| front_matter.get_str(key))
+        .map(str::trim)
+        .find(|value| is_w3c_date(value))
+        .map(str::to_string)
+}
+
+fn is_w3c_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() >= 10
+        && bytes[..10].iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -0,0 +1,145 @@
+use dioxus::prelude::*;
+use serde_json::json;
+
+use crate::{
+    utils::{
+        nav_tree::trail,
+        routing::{absolute_url, path_to_route},
+    },
+    Route, JSON_DB,
+};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct BreadcrumbsProps {
+    pub path: String,
+}
+
+#[derive(Clone, PartialEq)]
+struct Crumb {
+    name: String,
+    path: String,
+    route: Option<Route>,
+}
+
+fn crumbs_for(path: &str) -> Vec<Crumb> {
+    let Some(db_lock) = JSON_DB.get() else {
+        return Vec::new();
+    };
+    let db = db_lock.read().unwrap_or_else(|e| e.into_inner());
+
+    trail(db.site_tree(), path)
+        .into_iter()
+        .map(|node| Crumb {
+            name: node.name().to_string(),
+            path: node.path().to_string(),
+            // Directories without a landing page have nothing to show
+            route: db
+                .find_page(node.path())
+                .and_then(|_| path_to_route(node.path())),
+        })
+        .collect()
+}
+
+/// The trail as a schema.org `BreadcrumbList`, with `url_for` giving the
+/// address of each crumb that can be opened.
+fn json_ld(crumbs: &[Crumb], url_for: impl Fn(&str) -> Option<String>) -> String {
+    let items: Vec<_> = crumbs
+        .iter()
+        .enumerate()
+        .map(|(i, crumb)| {
+            let mut item = json!({
+                "@type": "ListItem",
+                "position": i + 1,
+                "name": crumb.name,
+            });
+            // A directory without a landing page has no address of its own
+            let url = crumb.route.as_ref().and_then(|_| url_for(&crumb.path));
+            if let Some(url) = url {
+                item["item"] = json!(url);
+            }
+            item
+        })
+        .collect();
+
+    json!({
+        "@context": "https://schema.org",
+        "@type": "BreadcrumbList",
+        "itemListElement": items,
+    })
+    .to_string()
+    // Keep the payload from closing its own <script> element
+    .replace("</", "<\\/")
+}
+
+#[component]
+pub fn Breadcrumbs(props: BreadcrumbsProps) -> Element {
+    let crumbs = crumbs_for(&props.path);
+    if crumbs.len() < 2 {
+        return rsx! {};
+    }
+
+    let structured_data = json_ld(&crumbs, absolute_url);
+    let last = crumbs.len() - 1;
+
+    rsx! {
+        nav {
+            class: "breadcrumbs",
+            aria_label: "Breadcrumb",
+            ol {
+                for (i, crumb) in crumbs.into_iter().enumerate() {
+                    li {
+                        key: "{crumb.path}",
+                        class: "breadcrumbs__item",
+                        match crumb.route {
+                            _ if i == last => rsx! {
+                                span { aria_current: "page", "{crumb.name}" }
+                            },
+                            Some(route) => rsx! {
+                                Link { to: route, "{crumb.name}" }
+                            },
+                            None => rsx! {
+                                span { "{crumb.name}" }
+                            },
+                        }
+                    }
+                }
+            }
+        }
+        script {
+            r#type: "application/ld+json",
+            dangerous_inner_html: "{structured_data}",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crumb(name: &str, path: &str, routable: bool) -> Crumb {
+        Crumb {
+            name: name.to_string(),
+            path: path.to_string(),
+            route: routable.then(|| path_to_route(path)).flatten(),
+        }
+    }
+
+    #[test]
+    fn only_routable_crumbs_link_in_structured_data() {
+        let crumbs = [
+            crumb("Pages", "/pages", false),
+            crumb("Guide", "/pages/guide", true),
+            crumb("Intro </script>", "/pages/guide/intro", true),
+        ];
+        let data = json_ld(&crumbs, |path| Some(format!("https://example.test{path}")));
+        assert!(!data.contains("</script>"), "{data}");
+
+        let data: serde_json::Value = serde_json::from_str(&data).unwrap();
+        let items = data["itemListElement"].as_array().unwrap();
+        assert_eq!(items.len(), 3);
+        assert!(items[0].get("item").is_none());
+        assert_eq!(items[1]["item"], "https://example.test/pages/guide");
+        assert_eq!(items[2]["position"], 3);
+        assert_eq!(items[2]["name"], "Intro </script>");
+    }
+}
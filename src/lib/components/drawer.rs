@@ -0,0 +1,261 @@
+use dioxus::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlElement;
+
+use super::{
+    nav::{focus_element, is_touch_pointer},
+    nav_entry::{NavEntry, NavStyle},
+};
+use crate::{
+    utils::{
+        hooks::{
+            drawer::{use_body_scroll_lock, use_close_on_back},
+            outside_hook::use_click_outside,
+        },
+        json_db::{normalize_path, NavNode},
+        nav_tree::trail,
+        routing::path_to_route,
+    },
+    Route,
+};
+
+const DRAWER_PANEL_ID: &str = "nav-drawer-panel";
+const DRAWER_CLOSE_ID: &str = "nav-drawer-close";
+/// How far, in CSS pixels, a swipe towards the edge must travel to close the drawer.
+const SWIPE_CLOSE_DISTANCE: f64 = 60.0;
+/// What Tab stops at inside the drawer.
+const FOCUSABLE: &str = "a[href], button:not([disabled])";
+
+#[derive(Props, Clone, PartialEq)]
+pub struct MobileDrawerProps {
+    pub label: String,
+    pub items: Vec<NavNode>,
+}
+
+/// A directory the reader drilled into, listed in place of its parent.
+#[derive(Clone, Debug, PartialEq)]
+struct Level {
+    name: String,
+    path: String,
+    items: Vec<NavNode>,
+}
+
+/// Keeps Tab inside the open drawer, as its `aria-modal` promises: from the
+/// last control focus wraps to the first, and from the first back to the
+/// last. Returns whether it did, so the browser's own move can be prevented.
+fn wrap_focus(backwards: bool) -> bool {
+    let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+        return false;
+    };
+    let Some(panel) = document.get_element_by_id(DRAWER_PANEL_ID) else {
+        return false;
+    };
+    let Ok(nodes) = panel.query_selector_all(FOCUSABLE) else {
+        return false;
+    };
+
+    let items: Vec<HtmlElement> = (0..nodes.length())
+        .filter_map(|i| nodes.item(i))
+        .filter_map(|node| node.dyn_into::<HtmlElement>().ok())
+        .collect();
+    let (Some(first), Some(last)) = (items.first(), items.last()) else {
+        return false;
+    };
+
+    let active = document.active_element();
+    let (edge, target) = if backwards { (first, last) } else { (last, first) };
+    if active.as_ref() != Some(&**edge) {
+        return false;
+    }
+    let _ = target.focus();
+    true
+}
+
+/// The drill-down levels leading to the directory that holds `current`.
+fn levels_for(items: &[NavNode], current: &str) -> Vec<Level> {
+    trail(items, current)
+        .into_iter()
+        .filter_map(|node| match node {
+            NavNode::Directory { name, path, children } => Some(Level {
+                name: name.clone(),
+                path: path.clone(),
+                items: children.clone(),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+#[component]
+pub fn MobileDrawer(props: MobileDrawerProps) -> Element {
+    let mut open = use_signal(|| false);
+    let mut levels = use_signal(Vec::<Level>::new);
+    let mut swipe_start = use_signal(|| None::<f64>);
+    let mut swipe_offset = use_signal(|| 0.0f64);
+    let current = normalize_path(&use_route::<Route>().to_string());
+
+    use_click_outside(DRAWER_PANEL_ID, open);
+    use_body_scroll_lock(open);
+    let follow_link = use_close_on_back(open);
+    let close = Callback::new(move |_| open.set(false));
+
+    use_effect(move || {
+        if open() {
+            focus_element(DRAWER_CLOSE_ID);
+        }
+    });
+
+    let finish_swipe = move |_: Event<PointerData>| {
+        if swipe_start.take().is_some() && swipe_offset() <= -SWIPE_CLOSE_DISTANCE {
+            open.set(false);
+        }
+        swipe_offset.set(0.0);
+    };
+
+    let level = levels.read().last().cloned();
+    let (title, entries) = match &level {
+        Some(level) => (level.name.clone(), level.items.clone()),
+        None => (props.label.clone(), props.items.clone()),
+    };
+    // A directory with a landing page lists it first, under its own name
+    let landing_page = level
+        .as_ref()
+        .filter(|level| path_to_route(&level.path).is_some())
+        .map(|level| NavNode::Page {
+            name: level.name.clone(),
+            path: level.path.clone(),
+        });
+    let items = props.items.clone();
+    let open_at = current.clone();
+
+    rsx! {
+        button {
+            class: "drawer-toggle",
+            r#type: "button",
+            aria_label: "Open navigation",
+            aria_haspopup: "dialog",
+            aria_expanded: "{open()}",
+            aria_controls: DRAWER_PANEL_ID,
+            onclick: move |e: Event<MouseData>| {
+                e.stop_propagation();
+                levels.set(levels_for(&items, &open_at));
+                open.set(true);
+            },
+            "☰"
+        }
+        div {
+            class: if open() { "nav-drawer nav-drawer--open" } else { "nav-drawer" },
+            div { class: "nav-drawer__backdrop" }
+            div {
+                id: DRAWER_PANEL_ID,
+                class: "nav-drawer__panel",
+                role: "dialog",
+                aria_modal: "true",
+                aria_label: "{props.label}",
+                aria_hidden: "{!open()}",
+                style: if swipe_offset() < 0.0 {
+                    format!("transform: translateX({}px); transition: none;", swipe_offset())
+                } else {
+                    String::new()
+                },
+                onkeydown: move |e: Event<KeyboardData>| {
+                    match e.key() {
+                        Key::Escape => {
+                            e.prevent_default();
+                            open.set(false);
+                        }
+                        Key::Tab if wrap_focus(e.modifiers().contains(Modifiers::SHIFT)) => {
+                            e.prevent_default();
+                        }
+                        _ => {}
+                    }
+                },
+                onpointerdown: move |e: Event<PointerData>| {
+                    if is_touch_pointer(&e) {
+                        swipe_start.set(Some(e.client_coordinates().x));
+                    }
+                },
+                onpointermove: move |e: Event<PointerData>| {
+                    if let Some(start) = swipe_start() {
+                        swipe_offset.set((e.client_coordinates().x - start).min(0.0));
+                    }
+                },
+                onpointerup: finish_swipe,
+                onpointercancel: finish_swipe,
+                div {
+                    class: "nav-drawer__header",
+                    if level.is_some() {
+                        button {
+                            class: "nav-drawer__back",
+                            r#type: "button",
+                            aria_label: "Back",
+                            onclick: move |_| {
+                                levels.write().pop();
+                            },
+                            "‹"
+                        }
+                    }
+                    span { class: "nav-drawer__title", "{title}" }
+                    button {
+                        id: DRAWER_CLOSE_ID,
+                        class: "nav-drawer__close",
+                        r#type: "button",
+                        aria_label: "Close navigation",
+                        onclick: move |_| open.set(false),
+                        "×"
+                    }
+                }
+                ul {
+                    class: "nav-drawer__list",
+                    if let Some(node) = landing_page {
+                        NavEntry {
+                            node,
+                            style: NavStyle::Drawer,
+                            navigate: follow_link,
+                            on_navigate: close,
+                        }
+                    }
+                    for node in entries {
+                        match node {
+                            NavNode::Directory { name, path, children } => {
+                                let in_trail = !trail(&children, &current).is_empty()
+                                    || normalize_path(&path) == current;
+                                let label = name.clone();
+                                rsx! {
+                                    li {
+                                        class: if in_trail {
+                                            "nav-drawer__item nav-drawer__item--active-trail"
+                                        } else {
+                                            "nav-drawer__item"
+                                        },
+                                        button {
+                                            class: "nav-drawer__link nav-drawer__link--section",
+                                            r#type: "button",
+                                            onclick: move |_| {
+                                                levels.write().push(Level {
+                                                    name: name.clone(),
+                                                    path: path.clone(),
+                                                    items: children.clone(),
+                                                });
+                                            },
+                                            "{label}"
+                                            span { aria_hidden: "true", "›" }
+                                        }
+                                    }
+                                }
+                            }
+                            node => rsx! {
+                                NavEntry {
+                                    node,
+                                    style: NavStyle::Drawer,
+                                    navigate: follow_link,
+                                    on_navigate: close,
+                                }
+                            },
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
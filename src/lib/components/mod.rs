@@ -1,7 +1,21 @@
+mod breadcrumbs;
+mod drawer;
 mod nav;
+mod nav_entry;
+mod not_found;
+mod page_head;
+mod page_nav;
 mod preview;
 mod scroll_top;
+mod sidebar;
+mod update_toast;
 
+pub use breadcrumbs::Breadcrumbs;
 pub use nav::NavBar;
+pub use not_found::NotFound;
+pub use page_head::PageHead;
+pub use page_nav::PageNav;
 pub use preview::PreviewArea;
 pub use scroll_top::ScrollToTop;
+pub use sidebar::Sidebar;
+pub use update_toast::UpdateToast;
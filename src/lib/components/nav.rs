@@ -1,35 +1,119 @@
-use crate::{utils::json_db::NavNode, Route};
+use super::{
+    drawer::MobileDrawer,
+    nav_entry::{NavEntry, NavStyle},
+};
+use crate::{
+    utils::{
+        json_db::{normalize_path, NavNode},
+        nav_tree::{root_entries, trail},
+        routing::path_to_route,
+    },
+    Route,
+};
 use dioxus::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Element as DomElement, HtmlElement};
+
+const ROOT_MENU_ID: &str = "main_menu";
+const ROOT_TRIGGER_ID: &str = "logo-menu-button";
 
 #[derive(Props, Clone, PartialEq)]
 pub struct NavBarProps {
     pub items: Vec<NavNode>,
 }
 
-fn path_to_route(path: &str) -> Option<Route> {
-    let trimmed_path = path.trim_start_matches('/');
+pub(super) fn is_touch_pointer(e: &Event<PointerData>) -> bool {
+    e.data.pointer_type() == "touch"
+}
+
+/// Which item of a menu should receive focus.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Focus {
+    First,
+    Last,
+    Next,
+    Prev,
+}
 
-    if trimmed_path.is_empty() {
-        return Some(Route::PageContent {
-            path: vec!["pages".to_string(), "home".to_string()],
-        });
+fn menu_id_for(path: &str) -> String {
+    let slug: String = path
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    format!("menu-{}", slug.trim_matches('-'))
+}
+
+pub(super) fn focus_element(id: &str) {
+    let element = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.get_element_by_id(id));
+    if let Some(element) = element.and_then(|element| element.dyn_into::<HtmlElement>().ok()) {
+        let _ = element.focus();
     }
+}
 
-    let segments: Vec<String> = trimmed_path
-        .split('/')
-        .filter(|s| !s.is_empty())
-        .map(|s| s.to_string())
+/// Moves focus between the items of the menu with id `menu_id`, relative to
+/// the item that currently has focus.
+fn focus_menu_item(menu_id: &str, focus: Focus) {
+    let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+        return;
+    };
+    let Some(menu) = document.get_element_by_id(menu_id) else {
+        return;
+    };
+    let Ok(nodes) = menu.query_selector_all(r#":scope > li > [role="menuitem"]"#) else {
+        return;
+    };
+
+    let items: Vec<DomElement> = (0..nodes.length())
+        .filter_map(|i| nodes.item(i))
+        .filter_map(|node| node.dyn_into::<DomElement>().ok())
         .collect();
+    if items.is_empty() {
+        return;
+    }
 
-    if !segments.is_empty() {
-        Some(Route::PageContent { path: segments })
-    } else {
-        None
+    let current = document
+        .active_element()
+        .and_then(|active| items.iter().position(|item| *item == active));
+    let index = match (focus, current) {
+        (Focus::First, _) | (Focus::Next, None) => 0,
+        (Focus::Last, _) | (Focus::Prev, None) => items.len() - 1,
+        (Focus::Next, Some(i)) => (i + 1) % items.len(),
+        (Focus::Prev, Some(i)) => (i + items.len() - 1) % items.len(),
+    };
+
+    if let Some(item) = items[index].dyn_ref::<HtmlElement>() {
+        let _ = item.focus();
     }
 }
 
-fn is_touch_pointer(e: &Event<PointerData>) -> bool {
-    e.data.pointer_type() == "touch"
+/// Keys that open a menu from its trigger, and where focus lands.
+fn opening_focus(key: &Key, horizontal: bool) -> Option<Focus> {
+    match key {
+        Key::Enter => Some(Focus::First),
+        Key::Character(c) if c == " " => Some(Focus::First),
+        Key::ArrowDown if !horizontal => Some(Focus::First),
+        Key::ArrowUp if !horizontal => Some(Focus::Last),
+        Key::ArrowRight if horizontal => Some(Focus::First),
+        _ => None,
+    }
+}
+
+/// Handles the arrow/Home/End keys shared by every menu. Returns whether the
+/// event was consumed.
+fn handle_menu_navigation(e: &Event<KeyboardData>, menu_id: &str) -> bool {
+    let focus = match e.key() {
+        Key::ArrowDown => Focus::Next,
+        Key::ArrowUp => Focus::Prev,
+        Key::Home => Focus::First,
+        Key::End => Focus::Last,
+        _ => return false,
+    };
+    e.prevent_default();
+    e.stop_propagation();
+    focus_menu_item(menu_id, focus);
+    true
 }
 
 #[derive(Clone, PartialEq, Props)]
@@ -42,36 +126,48 @@ struct MenuItemProps {
     on_close: Callback<()>,
 }
 
-#[derive(Clone, PartialEq, Props)]
-struct LinkItemProps {
-    label: String,
-    href: Route,
-    #[props(default = Callback::default())]
-    on_navigate: Callback<()>,
-}
-
 #[component]
 fn MenuItem(props: MenuItemProps) -> Element {
     let close_menu = props.on_close;
+    // Hooks come first: the children of a directory can change between
+    // renders, and with them whether this renders as a plain link
+    let mut is_open = use_signal(|| false);
+    let mut pending_focus = use_signal(|| None::<Focus>);
+    let current = use_route::<Route>().to_string();
+    let menu_id = menu_id_for(&props.path);
 
-    if props.items.is_empty() {
-        if let Some(route) = path_to_route(&props.path) {
-            return rsx! {
-                LinkItem {
-                    label: props.name,
-                    href: route,
-                    on_navigate: move |_| close_menu.call(()),
-                }
-            };
+    // Focus can only move into the submenu once it has been rendered open
+    let focus_menu_id = menu_id.clone();
+    use_effect(move || {
+        if let Some(focus) = pending_focus() {
+            focus_menu_item(&focus_menu_id, focus);
+            pending_focus.set(None);
         }
+    });
+
+    if props.items.is_empty() && path_to_route(&props.path).is_some() {
+        return rsx! {
+            NavEntry {
+                node: NavNode::Page { name: props.name, path: props.path },
+                style: NavStyle::Menu,
+                on_navigate: close_menu,
+            }
+        };
     }
 
-    let mut is_open = use_signal(|| false);
-    let folder_route = path_to_route(&props.path);
+    // Directory holding the current page somewhere below it, or serving it itself
+    let in_trail = normalize_path(&props.path) == normalize_path(&current)
+        || !trail(&props.items, &current).is_empty();
+    let has_landing_page = path_to_route(&props.path).is_some();
+    let trigger_id = format!("{menu_id}-trigger");
+
+    let keys_menu_id = menu_id.clone();
+    let keys_trigger_id = trigger_id.clone();
 
     rsx! {
         li {
-            class: "menu-item",
+            class: if in_trail { "menu-item menu-item--active-trail" } else { "menu-item" },
+            role: "none",
             onpointerenter: move |e: Event<PointerData>| {
                 if !is_touch_pointer(&e) {
                     is_open.set(true);
@@ -83,21 +179,53 @@ fn MenuItem(props: MenuItemProps) -> Element {
                 }
             },
             button {
+                id: "{trigger_id}",
                 class: "menu-label",
                 r#type: "button",
+                role: "menuitem",
+                tabindex: "-1",
+                aria_haspopup: "menu",
                 aria_expanded: "{is_open()}",
+                aria_controls: "{menu_id}",
+                aria_current: if in_trail { Some("true") } else { None },
                 onclick: move |e: Event<MouseData>| {
                     e.stop_propagation();
                     is_open.with_mut(|open| *open = !*open);
                 },
+                onkeydown: move |e: Event<KeyboardData>| {
+                    if let Some(focus) = opening_focus(&e.key(), true) {
+                        e.prevent_default();
+                        e.stop_propagation();
+                        is_open.set(true);
+                        pending_focus.set(Some(focus));
+                    }
+                },
                 "{props.name}"
             }
             ul {
+                id: "{menu_id}",
+                role: "menu",
+                aria_label: "{props.name}",
                 class: if is_open() { "dropdown-menu dropdown-open" } else { "dropdown-menu" },
-                if let Some(route) = folder_route {
-                    LinkItem {
-                        label: props.name.clone(),
-                        href: route,
+                onkeydown: move |e: Event<KeyboardData>| {
+                    if handle_menu_navigation(&e, &keys_menu_id) {
+                        return;
+                    }
+                    match e.key() {
+                        Key::Escape | Key::ArrowLeft => {
+                            e.prevent_default();
+                            e.stop_propagation();
+                            is_open.set(false);
+                            focus_element(&keys_trigger_id);
+                        }
+                        Key::Tab => is_open.set(false),
+                        _ => {}
+                    }
+                },
+                if has_landing_page {
+                    NavEntry {
+                        node: NavNode::Page { name: props.name.clone(), path: props.path.clone() },
+                        style: NavStyle::Menu,
                         on_navigate: move |_| {
                             is_open.set(false);
                             close_menu.call(());
@@ -106,36 +234,24 @@ fn MenuItem(props: MenuItemProps) -> Element {
                 }
                 for item in props.items {
                     match item {
-                        NavNode::Page { name, path } => {
-                            if let Some(route) = path_to_route(&path) {
-                                rsx! {
-                                    LinkItem {
-                                        label: name,
-                                        href: route,
-                                        on_navigate: move |_| {
-                                            is_open.set(false);
-                                            close_menu.call(());
-                                        },
-                                    }
-                                }
-                            } else {
-                                rsx! {
-                                    li {
-                                        span { "{name} (Invalid path: {path})" }
-                                    }
-                                }
+                        NavNode::Directory { name, path, children } => rsx! {
+                            MenuItem {
+                                name,
+                                path,
+                                items: children,
+                                on_close: close_menu,
                             }
                         },
-                        NavNode::Directory { name, path, children, .. } => {
-                            rsx! {
-                                MenuItem {
-                                    name: name.clone(),
-                                    path: path.clone(),
-                                    items: children.clone(),
-                                    on_close: close_menu,
-                                }
+                        node => rsx! {
+                            NavEntry {
+                                node,
+                                style: NavStyle::Menu,
+                                on_navigate: move |_| {
+                                    is_open.set(false);
+                                    close_menu.call(());
+                                },
                             }
-                        }
+                        },
                     }
                 }
             }
@@ -143,35 +259,42 @@ fn MenuItem(props: MenuItemProps) -> Element {
     }
 }
 
-#[component]
-fn LinkItem(props: LinkItemProps) -> Element {
-    let route = props.href.clone();
-    let on_nav = props.on_navigate;
-    rsx! {
-        li {
-            Link {
-                to: route,
-                class: "link-item",
-                onclick: move |_| on_nav.call(()),
-                "{props.label}"
-            }
-        }
-    }
-}
-
 #[component]
 pub fn NavBar(props: NavBarProps) -> Element {
     let mut root_open = use_signal(|| false);
+    let mut pending_focus = use_signal(|| None::<Focus>);
     let close_menu = Callback::new(move |_| root_open.set(false));
 
     crate::utils::hooks::outside_hook::use_click_outside("logo-menu", root_open);
 
+    use_effect(move || {
+        if let Some(focus) = pending_focus() {
+            focus_menu_item(ROOT_MENU_ID, focus);
+            pending_focus.set(None);
+        }
+    });
+
+    if props.items.is_empty() {
+        return rsx! {
+            nav { class: "navbar", aria_label: "Main" }
+        };
+    }
+    let (root, entries) = root_entries(&props.items);
+    let label = root.unwrap_or("Menu").to_string();
+    let entries = entries.to_vec();
+
     rsx! {
         nav {
             class: "navbar",
-            if let NavNode::Directory { name, children, .. } = &props.items[0] {
+            aria_label: "Main",
+            MobileDrawer { label: label.clone(), items: entries.clone() }
+            ul {
+                class: "navbar-menubar",
+                role: "menubar",
+                aria_label: "Site navigation",
                 li {
                     id: "logo-menu",
+                    role: "none",
                     class: if root_open() {
                         "menu-item logo-menu logo-menu--open"
                     } else {
@@ -188,49 +311,70 @@ pub fn NavBar(props: NavBarProps) -> Element {
                         }
                     },
                     button {
+                        id: ROOT_TRIGGER_ID,
                         class: "logo menu-label",
                         r#type: "button",
+                        role: "menuitem",
+                        tabindex: "0",
                         aria_label: "Open navigation menu",
+                        aria_haspopup: "menu",
                         aria_expanded: "{root_open()}",
-                        aria_controls: "main_menu",
+                        aria_controls: ROOT_MENU_ID,
                         onclick: move |e: Event<MouseData>| {
                             e.stop_propagation();
                             root_open.with_mut(|open| *open = !*open);
                         },
-                        "{name}"
+                        onkeydown: move |e: Event<KeyboardData>| {
+                            if e.key() == Key::Escape {
+                                root_open.set(false);
+                            } else if let Some(focus) = opening_focus(&e.key(), false) {
+                                e.prevent_default();
+                                root_open.set(true);
+                                pending_focus.set(Some(focus));
+                            }
+                        },
+                        "{label}"
                     }
                     ul {
-                        id: "main_menu",
+                        id: ROOT_MENU_ID,
+                        role: "menu",
+                        aria_label: "{label}",
                         class: if root_open() {
                             "navbar-nav dropdown-menu dropdown-open"
                         } else {
                             "navbar-nav dropdown-menu"
                         },
-                        for item in children.iter().cloned() {
+                        onkeydown: move |e: Event<KeyboardData>| {
+                            if handle_menu_navigation(&e, ROOT_MENU_ID) {
+                                return;
+                            }
+                            match e.key() {
+                                Key::Escape => {
+                                    e.prevent_default();
+                                    root_open.set(false);
+                                    focus_element(ROOT_TRIGGER_ID);
+                                }
+                                Key::Tab => root_open.set(false),
+                                _ => {}
+                            }
+                        },
+                        for item in entries {
                             match item {
-                                NavNode::Page { name, path } => {
-                                    if let Some(route) = path_to_route(&path) {
-                                        rsx! {
-                                            LinkItem {
-                                                label: name,
-                                                href: route,
-                                                on_navigate: close_menu,
-                                            }
-                                        }
-                                    } else {
-                                        rsx! {}
+                                NavNode::Directory { name, path, children } => rsx! {
+                                    MenuItem {
+                                        name,
+                                        path,
+                                        items: children,
+                                        on_close: close_menu,
                                     }
                                 },
-                                NavNode::Directory { name, path, children, .. } => {
-                                    rsx! {
-                                        MenuItem {
-                                            name: name,
-                                            path: path,
-                                            items: children,
-                                            on_close: close_menu,
-                                        }
+                                node => rsx! {
+                                    NavEntry {
+                                        node,
+                                        style: NavStyle::Menu,
+                                        on_navigate: close_menu,
                                     }
-                                }
+                                },
                             }
                         }
                     }
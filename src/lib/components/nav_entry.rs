@@ -0,0 +1,155 @@
+use dioxus::prelude::*;
+
+use crate::{
+    utils::{
+        json_db::{normalize_path, NavNode},
+        routing::{path_to_route, resolve_link},
+    },
+    Route,
+};
+
+/// Which navigation an entry is drawn in, and so which classes it takes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum NavStyle {
+    /// The dropdown menus of the navbar, laid out as ARIA menus.
+    Menu,
+    Sidebar,
+    Drawer,
+}
+
+impl NavStyle {
+    fn item_class(self) -> Option<&'static str> {
+        match self {
+            NavStyle::Menu => None,
+            NavStyle::Sidebar => Some("sidebar__item"),
+            NavStyle::Drawer => Some("nav-drawer__item"),
+        }
+    }
+
+    fn link_class(self) -> &'static str {
+        match self {
+            NavStyle::Menu => "link-item",
+            NavStyle::Sidebar => "sidebar__link",
+            NavStyle::Drawer => "nav-drawer__link",
+        }
+    }
+
+    fn separator_class(self) -> &'static str {
+        match self {
+            NavStyle::Menu => "menu-separator",
+            NavStyle::Sidebar => "sidebar__separator",
+            NavStyle::Drawer => "nav-drawer__separator",
+        }
+    }
+
+    fn heading_class(self) -> &'static str {
+        match self {
+            NavStyle::Menu => "menu-heading",
+            NavStyle::Sidebar => "sidebar__heading",
+            NavStyle::Drawer => "nav-drawer__heading",
+        }
+    }
+
+    fn in_menu(self) -> bool {
+        self == NavStyle::Menu
+    }
+}
+
+#[derive(Props, Clone, PartialEq)]
+pub(super) struct NavEntryProps {
+    node: NavNode,
+    style: NavStyle,
+    /// Follows links into the site in place of the router, for navigations
+    /// that need a say in how the route enters the history.
+    #[props(default)]
+    navigate: Option<Callback<Route>>,
+    #[props(default = Callback::default())]
+    on_navigate: Callback<()>,
+}
+
+/// A page, link, separator or heading of the navigation tree. Directories
+/// are left to each navigation, which lays them out its own way.
+///
+/// Links into the site go through the router like any page; anything else is
+/// a plain anchor, opened in a new tab when external.
+#[component]
+pub(super) fn NavEntry(props: NavEntryProps) -> Element {
+    let style = props.style;
+    let current = normalize_path(&use_route::<Route>().to_string());
+    let navigate = props.navigate;
+    let on_navigate = props.on_navigate;
+    let role = style.in_menu().then_some("menuitem");
+    let tabindex = style.in_menu().then_some("-1");
+
+    let (name, route, url, external) = match props.node {
+        NavNode::Page { name, path } => (name, path_to_route(&path), String::new(), false),
+        NavNode::Link { name, url, external } => {
+            let route = resolve_link(&url, "/")
+                .and_then(|path| path_to_route(&path))
+                .filter(|_| !external);
+            (name, route, url, external)
+        }
+        NavNode::Separator => {
+            return rsx! {
+                li { class: style.separator_class(), role: "separator" }
+            };
+        }
+        NavNode::Heading { name } => {
+            return rsx! {
+                li {
+                    class: style.heading_class(),
+                    role: style.in_menu().then_some("presentation"),
+                    "{name}"
+                }
+            };
+        }
+        NavNode::Directory { .. } => return rsx! {},
+    };
+
+    let base = style.link_class();
+    let entry = match route {
+        Some(route) => {
+            let is_active = normalize_path(&route.to_string()) == current;
+            let target = route.clone();
+            rsx! {
+                Link {
+                    class: if is_active { format!("{base} {base}--active") } else { base.to_string() },
+                    aria_current: if is_active { Some("page") } else { None },
+                    role,
+                    tabindex,
+                    to: route,
+                    onclick_only: navigate.is_some(),
+                    onclick: move |_| {
+                        if let Some(navigate) = navigate {
+                            navigate.call(target.clone());
+                        }
+                        on_navigate.call(());
+                    },
+                    "{name}"
+                }
+            }
+        }
+        // A page outside the router has nowhere to go
+        None if url.is_empty() => return rsx! {},
+        None => rsx! {
+            a {
+                class: if external { format!("{base} {base}--external") } else { base.to_string() },
+                href: "{url}",
+                target: if external { Some("_blank") } else { None },
+                rel: if external { Some("noopener noreferrer external") } else { None },
+                role,
+                tabindex,
+                onclick: move |_| on_navigate.call(()),
+                "{name}"
+            }
+        },
+    };
+
+    rsx! {
+        li {
+            class: style.item_class(),
+            role: style.in_menu().then_some("none"),
+            {entry}
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use dioxus::prelude::*;
+
+use crate::{
+    utils::{
+        fuzzy::suggest,
+        routing::{home_route, path_to_route},
+    },
+    JSON_DB,
+};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct NotFoundProps {
+    pub path: String,
+}
+
+#[component]
+pub fn NotFound(props: NotFoundProps) -> Element {
+    let mut query = use_signal(|| props.path.clone());
+    // Another missing page starts its search over from its own path
+    let path = props.path.clone();
+    use_effect(use_reactive!(|path| query.set(path)));
+
+    let pages = JSON_DB
+        .get()
+        .map(|db_lock| db_lock.read().unwrap_or_else(|e| e.into_inner()).page_entries())
+        .unwrap_or_default();
+    let suggestions: Vec<(String, String)> = suggest(&query(), &pages, 8)
+        .into_iter()
+        .cloned()
+        .collect();
+
+    rsx! {
+        section {
+            class: "not-found",
+            h1 { "Page not found" }
+            p {
+                class: "not-found__path",
+                "Nothing lives at "
+                code { "/{props.path}" }
+                "."
+            }
+            input {
+                class: "not-found__search",
+                r#type: "search",
+                placeholder: "Search pages",
+                aria_label: "Search pages",
+                value: "{query}",
+                oninput: move |e| query.set(e.value()),
+            }
+            if suggestions.is_empty() {
+                p { class: "not-found__empty", "No similar pages found." }
+            } else {
+                p { "Were you looking for:" }
+                ul {
+                    class: "not-found__suggestions",
+                    for (name, page_path) in suggestions {
+                        if let Some(route) = path_to_route(&page_path) {
+                            li {
+                                key: "{page_path}",
+                                Link { to: route, "{name}" }
+                                span { class: "not-found__suggestion-path", "{page_path}" }
+                            }
+                        }
+                    }
+                }
+            }
+            Link {
+                class: "not-found__home",
+                to: home_route(),
+                "Back to home"
+            }
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use dioxus::prelude::*;
+
+use crate::utils::head::{HeadTag, PageMeta};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct PageHeadProps {
+    pub meta: PageMeta,
+}
+
+/// Replaces the static `<title>` and adds the description, canonical URL,
+/// Open Graph and Twitter card tags of the page being shown.
+#[component]
+pub fn PageHead(props: PageHeadProps) -> Element {
+    rsx! {
+        document::Title { "{props.meta.document_title()}" }
+        for tag in props.meta.tags() {
+            match tag {
+                HeadTag::Name { name, content } => rsx! {
+                    document::Meta { name, content }
+                },
+                HeadTag::Property { property, content } => rsx! {
+                    document::Meta { property, content }
+                },
+                HeadTag::Canonical(href) => rsx! {
+                    document::Link { rel: "canonical", href }
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use dioxus::prelude::*;
+
+use crate::{
+    utils::{json_db::NavNode, nav_tree, routing::path_to_route},
+    Route, JSON_DB,
+};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct PageNavProps {
+    pub path: String,
+}
+
+/// A neighbouring page as `(name, route)`.
+type Neighbour = Option<(String, Route)>;
+
+/// Previous and next page in the same directory as `path`.
+fn neighbours(path: &str) -> (Neighbour, Neighbour) {
+    let Some(db_lock) = JSON_DB.get() else {
+        return (None, None);
+    };
+    let db = db_lock.read().unwrap_or_else(|e| e.into_inner());
+
+    let link = |node: Option<&NavNode>| {
+        let node = node?;
+        Some((node.name().to_string(), path_to_route(node.path())?))
+    };
+    let (prev, next) = nav_tree::neighbours(db.nav_tree(), path);
+    (link(prev), link(next))
+}
+
+#[component]
+pub fn PageNav(props: PageNavProps) -> Element {
+    let (prev, next) = neighbours(&props.path);
+    if prev.is_none() && next.is_none() {
+        return rsx! {};
+    }
+
+    rsx! {
+        nav {
+            class: "page-nav",
+            aria_label: "Pages in this section",
+            if let Some((name, route)) = prev {
+                Link {
+                    class: "page-nav__link page-nav__link--prev",
+                    to: route,
+                    rel: "prev",
+                    span { class: "page-nav__label", "← Previous (k)" }
+                    span { class: "page-nav__title", "{name}" }
+                }
+            }
+            if let Some((name, route)) = next {
+                Link {
+                    class: "page-nav__link page-nav__link--next",
+                    to: route,
+                    rel: "next",
+                    span { class: "page-nav__label", "Next (j) →" }
+                    span { class: "page-nav__title", "{name}" }
+                }
+            }
+        }
+    }
+}
@@ -1,16 +1,33 @@
 use dioxus::prelude::*;
 
+use super::{Breadcrumbs, PageNav};
+use crate::utils::page_stats::PageStats;
+
 #[derive(Props, Clone, PartialEq)]
 pub struct PreviewAreaProps {
     pub content: String,
+    pub path: String,
+    pub stats: PageStats,
 }
 
 #[component]
 pub fn PreviewArea(props: PreviewAreaProps) -> Element {
     rsx! {
+        Breadcrumbs { path: props.path.clone() }
+        p {
+            class: "page-meta",
+            span { "{props.stats.reading_minutes()} min read" }
+            span { aria_hidden: "true", " · " }
+            span { "{props.stats.words} words" }
+            if props.stats.code_words > 0 {
+                span { aria_hidden: "true", " · " }
+                span { "{props.stats.code_words} in code" }
+            }
+        }
         article {
             class: "markdown-body",
             dangerous_inner_html: "{props.content}"
         }
+        PageNav { path: props.path.clone() }
     }
 }
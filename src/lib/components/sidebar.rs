@@ -0,0 +1,157 @@
+use std::collections::BTreeSet;
+
+use dioxus::prelude::*;
+use gloo_storage::{LocalStorage, Storage};
+use web_sys::{ScrollIntoViewOptions, ScrollLogicalPosition};
+
+use super::nav_entry::{NavEntry, NavStyle};
+use crate::utils::{
+    json_db::{normalize_path, NavNode},
+    nav_tree::{root_entries, trail},
+    routing::path_to_route,
+};
+
+const OPEN_SECTIONS_KEY: &str = "sidebar-open";
+
+#[derive(Props, Clone, PartialEq)]
+pub struct SidebarProps {
+    pub items: Vec<NavNode>,
+    pub current: String,
+}
+
+#[derive(Props, Clone, PartialEq)]
+struct SidebarNodeProps {
+    node: NavNode,
+    current: String,
+    open: Signal<BTreeSet<String>>,
+}
+
+fn save_open_sections(open: &BTreeSet<String>) {
+    let _ = LocalStorage::set(OPEN_SECTIONS_KEY, open);
+}
+
+fn scroll_active_into_view() {
+    let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+        return;
+    };
+    let Ok(Some(active)) = document.query_selector(r#".sidebar [aria-current="page"]"#) else {
+        return;
+    };
+    let options = ScrollIntoViewOptions::new();
+    options.set_block(ScrollLogicalPosition::Nearest);
+    active.scroll_into_view_with_scroll_into_view_options(&options);
+}
+
+#[component]
+pub fn Sidebar(props: SidebarProps) -> Element {
+    let mut open = use_signal(|| {
+        LocalStorage::get::<BTreeSet<String>>(OPEN_SECTIONS_KEY).unwrap_or_default()
+    });
+
+    let items = root_entries(&props.items).1.to_vec();
+
+    // Expand the sections leading to the current page and bring it into view
+    let tree = items.clone();
+    let current_path = props.current.clone();
+    use_effect(use_reactive!(|current_path| {
+        let ancestors: Vec<String> = trail(&tree, &current_path)
+            .into_iter()
+            .filter(|node| matches!(node, NavNode::Directory { .. }))
+            .map(|node| normalize_path(node.path()))
+            .collect();
+
+        let missing = ancestors.iter().any(|path| !open.peek().contains(path));
+        if missing {
+            let mut sections = open.write();
+            sections.extend(ancestors);
+            save_open_sections(&sections);
+        }
+
+        scroll_active_into_view();
+    }));
+    let current = props.current.clone();
+
+    rsx! {
+        aside {
+            class: "sidebar",
+            nav {
+                aria_label: "Documentation",
+                ul {
+                    class: "sidebar__tree",
+                    for (i, node) in items.into_iter().enumerate() {
+                        SidebarNode {
+                            key: "{i}-{node.path()}",
+                            node: node.clone(),
+                            current: current.clone(),
+                            open,
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn SidebarNode(props: SidebarNodeProps) -> Element {
+    let mut open = props.open;
+    let is_active = !props.node.path().is_empty()
+        && normalize_path(props.node.path()) == normalize_path(&props.current);
+
+    match props.node {
+        NavNode::Directory { name, path, children } => {
+            let key = normalize_path(&path);
+            let expanded = open.read().contains(&key);
+            let route = path_to_route(&path);
+
+            rsx! {
+                li {
+                    class: "sidebar__item sidebar__item--section",
+                    div {
+                        class: "sidebar__row",
+                        button {
+                            class: if expanded { "sidebar__toggle sidebar__toggle--open" } else { "sidebar__toggle" },
+                            r#type: "button",
+                            aria_expanded: "{expanded}",
+                            aria_label: if expanded { "Collapse {name}" } else { "Expand {name}" },
+                            onclick: move |_| {
+                                let mut sections = open.write();
+                                if !sections.remove(&key) {
+                                    sections.insert(key.clone());
+                                }
+                                save_open_sections(&sections);
+                            },
+                            "›"
+                        }
+                        if let Some(route) = route {
+                            Link {
+                                class: if is_active { "sidebar__link sidebar__link--active" } else { "sidebar__link" },
+                                aria_current: if is_active { Some("page") } else { None },
+                                to: route,
+                                "{name}"
+                            }
+                        } else {
+                            span { class: "sidebar__link", "{name}" }
+                        }
+                    }
+                    if expanded {
+                        ul {
+                            class: "sidebar__children",
+                            for (i, child) in children.into_iter().enumerate() {
+                                SidebarNode {
+                                    key: "{i}-{child.path()}",
+                                    node: child.clone(),
+                                    current: props.current.clone(),
+                                    open,
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        node => rsx! {
+            NavEntry { node, style: NavStyle::Sidebar }
+        },
+    }
+}
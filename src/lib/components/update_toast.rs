@@ -0,0 +1,35 @@
+use dioxus::prelude::*;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct UpdateToastProps {
+    pub on_apply: Callback<()>,
+    pub on_dismiss: Callback<()>,
+}
+
+#[component]
+pub fn UpdateToast(props: UpdateToastProps) -> Element {
+    let on_apply = props.on_apply;
+    let on_dismiss = props.on_dismiss;
+
+    rsx! {
+        div {
+            class: "update-toast",
+            role: "status",
+            aria_live: "polite",
+            span { class: "update-toast__text", "New content available" }
+            button {
+                class: "update-toast__apply",
+                r#type: "button",
+                onclick: move |_| on_apply.call(()),
+                "Refresh"
+            }
+            button {
+                class: "update-toast__dismiss",
+                r#type: "button",
+                aria_label: "Dismiss",
+                onclick: move |_| on_dismiss.call(()),
+                "×"
+            }
+        }
+    }
+}
@@ -1,19 +1,58 @@
 use std::sync::OnceLock;
-use dioxus::prelude::*;
-use syntect::{highlighting::ThemeSet, parsing::SyntaxSet};
+use dioxus::{dioxus_core::Task, logger::tracing, prelude::*};
 
 mod components;
 mod utils;
 
-use components::{NavBar, PreviewArea, ScrollToTop};
-use utils::json_db::{DataError, JsonDb};
+#[cfg(feature = "cli")]
+pub mod cli;
+
+// The content lifecycle with swappable backends, to run it outside the browser
+pub use utils::backend::{
+    BrowserStorage, HttpClient, HttpResponse, MemoryStorage, ReqwestClient, Storage,
+};
+pub use utils::json_db::{ContentLoader, ContentSource, DataError, JsonDb, PageSource};
+// Rendering and the plugin API for custom markdown transforms
+pub use utils::plugins::{
+    CodeHighlight, ImageFigures, InternalLinks, MarkdownPlugin, PageInfo, PluginRegistry,
+};
+pub use utils::render::{
+    markdown_to_html, summarize, PageSummary, RenderContext, RenderOptions, RenderedBody,
+};
+
+use components::{NavBar, NotFound, PageHead, PreviewArea, ScrollToTop, Sidebar, UpdateToast};
+use utils::head::PageMeta;
+use utils::index::Layout;
+use utils::json_db::{PendingRevision, RenderedPage};
+use utils::routing::absolute_url;
 
 // Static resources
 pub static BASE_URL: &str = "https://raw.githubusercontent.com/amaali7/markdown_files/refs/heads/main/MarkDown";
-pub static SYNTAX_SET: OnceLock<RwLock<SyntaxSet>> = OnceLock::new();
-pub static THEME_SET: OnceLock<ThemeSet> = OnceLock::new();
+/// Latest commit of the content repository, whose hash is the content revision.
+pub static COMMITS_URL: &str = "https://api.github.com/repos/amaali7/markdown_files/commits?sha=main&per_page=1";
+/// Address of a running `blogy-serve`, set when building the app for local
+/// authoring: `BLOGY_DEV_SERVER=http://localhost:8787 dx serve`.
+pub static DEV_SERVER: Option<&str> = option_env!("BLOGY_DEV_SERVER");
 use std::sync::RwLock;
 pub static JSON_DB: OnceLock<RwLock<JsonDb>> = OnceLock::new();
+/// Bumped every time `JSON_DB` is (re)installed so views depending on it re-render.
+pub static DB_REVISION: GlobalSignal<u64> = Signal::global(|| 0);
+
+/// Where `index.json`, the markdown files and their images are fetched from.
+pub fn content_base() -> &'static str {
+    DEV_SERVER.unwrap_or(BASE_URL)
+}
+
+fn install_db(db: JsonDb) {
+    utils::page_cache::page_cache().retain_revision(db.revision());
+    match JSON_DB.get() {
+        Some(db_lock) => *db_lock.write().unwrap_or_else(|e| e.into_inner()) = db,
+        None => {
+            let _ = JSON_DB.set(RwLock::new(db));
+        }
+    }
+    *DB_REVISION.write() += 1;
+}
 
 
 // Remove the global content state and use local state instead
@@ -35,34 +74,77 @@ fn PageContent(path: Vec<String>) -> Element {
         return rsx!( div { "Redirecting…" } );
     }
 
+    let nav = navigator();
+    let render = use_context::<RenderContext>();
+    let load_render = render.clone();
     let content_state = use_signal(|| ContentState::Loading);
+    let mut load_task = use_signal(|| None::<Task>);
+    let mut load_token = use_signal(|| 0u64);
+    let revision = DB_REVISION();
 
     // Use use_effect with path dependency to trigger on route changes
-    use_effect(use_reactive!( |path| {
+    use_effect(use_reactive!( |(path, revision)| {
+        let _ = revision;
         let path_ = path.join("/").to_string();
-        let mut content_state = content_state.clone();
+        let mut content_state = content_state;
+        let render = load_render.clone();
 
-        spawn(async move {
+        // Only the latest navigation may touch `content_state`. Cancelling the
+        // previous task drops its future, which aborts any in-flight request;
+        // the token guards against a result that was already on its way.
+        if let Some(task) = load_task.take() {
+            task.cancel();
+        }
+        let token = {
+            let mut current = load_token.write();
+            *current += 1;
+            *current
+        };
+
+        let task = spawn(async move {
             *content_state.write() = ContentState::Loading;
 
-            match load_content(&path_).await {
+            let result = load_content(&path_, &render).await;
+            if *load_token.peek() != token {
+                return;
+            }
+
+            match result {
+                Ok(ContentState::Redirect(target)) => {
+                    nav.replace(format!("/{}", target.trim_start_matches('/')).as_str());
+                }
                 Ok(state) => *content_state.write() = state,
+                Err(DataError::PageNotFound) => {
+                    *content_state.write() = ContentState::NotFound(path_);
+                }
                 Err(e) => {
                     eprintln!("Content loading error: {}", e);
                     *content_state.write() = ContentState::Error(e.to_string());
                 }
             }
         });
+        load_task.set(Some(task));
     }));
 
     match content_state() {
         ContentState::Loading => rsx! { LoadingSpinner {} },
-        ContentState::Error(e) => rsx! { ErrorMessage { error: e.clone() } },
-        ContentState::Ready(content) => rsx! {
+        ContentState::Error(e) => rsx! {
+            PageHead { meta: PageMeta::titled("Error") }
+            ErrorMessage { error: e.clone() }
+        },
+        ContentState::Ready(page) => rsx! {
+            PageHead { meta: page_meta(&page, &render) }
             PreviewArea {
-                content: content.clone(),
+                content: page.html.clone(),
+                path: page.path.clone(),
+                stats: page.stats,
             }
-        }
+        },
+        ContentState::NotFound(path) => rsx! {
+            PageHead { meta: PageMeta::titled("Page not found") }
+            NotFound { path }
+        },
+        ContentState::Redirect(_) => rsx! { LoadingSpinner {} },
     }
 }
 
@@ -70,6 +152,15 @@ fn PageContent(path: Vec<String>) -> Element {
 #[component]
 pub fn AppContent() -> Element {
     let compact = utils::hooks::scroll_header::use_header_compact(48.0);
+    utils::hooks::keyboard::use_page_nav_shortcuts();
+    let _revision = DB_REVISION();
+
+    let Route::PageContent { path } = use_route::<Route>();
+    let current_path = format!("/{}", path.join("/"));
+    let layout = JSON_DB
+        .get()
+        .map(|db_lock| db_lock.read().unwrap_or_else(|e| e.into_inner()).layout_for(&current_path))
+        .unwrap_or_default();
 
     rsx! {
         match JSON_DB.get() {
@@ -86,8 +177,25 @@ pub fn AppContent() -> Element {
                 header { class: "site-header", div { "Loading navigation..." } }
             }
         }
-        main { class: "site-main",
-            Outlet::<Route> {}
+        match layout {
+            Layout::Docs => rsx! {
+                main { class: "site-main site-main--docs",
+                    if let Some(db_lock) = JSON_DB.get() {
+                        Sidebar {
+                            items: db_lock.read().unwrap_or_else(|e| e.into_inner()).get_nav_tree(),
+                            current: current_path.clone(),
+                        }
+                    }
+                    div { class: "site-content",
+                        Outlet::<Route> {}
+                    }
+                }
+            },
+            Layout::Blog => rsx! {
+                main { class: "site-main",
+                    Outlet::<Route> {}
+                }
+            },
         }
         ScrollToTop {}
     }
@@ -95,21 +203,60 @@ pub fn AppContent() -> Element {
 
 #[component]
 pub fn App() -> Element {
+    let mut pending = use_signal(|| None::<PendingRevision>);
+    let content_changes = utils::hooks::live_reload::use_live_reload();
+    use_context_provider(RenderContext::app);
+
+    // The dev server reports every saved file, reload the index and with it
+    // the page being viewed
+    use_effect(move || {
+        if content_changes() == 0 {
+            return;
+        }
+        spawn(async move {
+            match ContentLoader::browser().load().await {
+                Ok(db) => install_db(db),
+                Err(e) => tracing::warn!("Reloading content failed: {}", e),
+            }
+        });
+    });
+
+    // Render from the cached index straight away and only block on the
+    // network when there is nothing cached yet.
     let init = use_resource(move || async move {
-        utils::syntax::init_syntax_highlighter()
-            .await
-            .map_err(|e| DataError::SyntaxInit(e))?;
-        JsonDb::load().await
+        // A stale cache would only flash old content while authoring
+        let loader = ContentLoader::browser();
+        match loader.load_cached().filter(|_| DEV_SERVER.is_none()) {
+            Some(cached) => {
+                install_db(cached);
+                spawn(async move {
+                    match ContentLoader::browser().check_revision().await {
+                        Ok(Some(revision)) => pending.set(Some(revision)),
+                        Ok(None) => {}
+                        Err(e) => tracing::warn!("Revision check failed: {}", e),
+                    }
+                });
+            }
+            None => install_db(loader.load().await?),
+        }
+        Ok::<(), DataError>(())
     });
 
     match &*init.read_unchecked() {
-        Some(Ok(jsondb)) => {
-            if JSON_DB.get().is_none() {
-                JSON_DB.get_or_init(|| RwLock::new(jsondb.clone()));
-            }
+        Some(Ok(())) => {
             rsx! {
                 link { rel: "stylesheet", href: asset!("/styles/main.scss") }
                 Router::<Route>{}
+                if pending.read().is_some() {
+                    UpdateToast {
+                        on_apply: move |_| {
+                            if let Some(revision) = pending.take() {
+                                install_db(ContentLoader::browser().apply(revision));
+                            }
+                        },
+                        on_dismiss: move |_| pending.set(None),
+                    }
+                }
             }
         },
         Some(Err(e)) => rsx! { p { "Loading failed, {e}" } },
@@ -121,30 +268,55 @@ pub fn App() -> Element {
 enum ContentState {
     Loading,
     Error(String),
-    Ready(String),
+    Ready(RenderedPage),
+    NotFound(String),
+    Redirect(String),
 }
 
-async fn load_content(path: &str) -> Result<ContentState, DataError> {
-    let db_lock = JSON_DB.get().unwrap();
-    let db = db_lock.read().unwrap();
+async fn load_content(path: &str, render: &RenderContext) -> Result<ContentState, DataError> {
+    let db_lock = JSON_DB.get().ok_or(DataError::PageNotFound)?;
 
-    // Normalize the path
-    let normalized_path = if path.starts_with('/') {
-        path.to_string()
-    } else {
-        format!("/{}", path)
+    // Resolve the page while holding the lock, then release it before awaiting
+    let source = {
+        let db = db_lock.read().unwrap_or_else(|e| e.into_inner());
+        match db.page_source(path) {
+            Err(DataError::PageNotFound) => {
+                return match db.redirect_for(path) {
+                    Some(target) => Ok(ContentState::Redirect(target)),
+                    None => Err(DataError::PageNotFound),
+                };
+            }
+            source => source?,
+        }
     };
 
-    let found_page = db.find_page(&normalized_path);
+    let page = ContentLoader::browser().render(&source, render).await?;
+    db_lock
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .register_aliases(&page.path, &page.front_matter);
+    Ok(ContentState::Ready(page))
+}
 
-    if let Some((section, page)) = found_page {
-        let mut db_clone = db.clone();
+fn page_meta(page: &RenderedPage, render: &RenderContext) -> PageMeta {
+    let name = JSON_DB
+        .get()
+        .and_then(|db_lock| {
+            db_lock
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .page_name(&page.path)
+                .map(str::to_string)
+        })
+        .unwrap_or_default();
 
-        let content = db_clone.get_html_content(section, page).await?;
-        Ok(ContentState::Ready(content))
-    } else {
-        Err(DataError::PageNotFound)
-    }
+    PageMeta::from_page(
+        &name,
+        &page.front_matter,
+        &page.excerpt,
+        absolute_url(&page.path),
+        |image| render.image_url(image, &page.path),
+    )
 }
 
 #[component]
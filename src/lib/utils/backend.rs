@@ -0,0 +1,184 @@
+//! What `ContentLoader` needs from its environment: an HTTP client for the
+//! revision, the index and the pages, and a key-value store to cache them.
+//! The browser uses `reqwest` and `LocalStorage`; native code can bring its
+//! own, e.g. `MemoryStorage` against a local stub server.
+
+use std::{collections::HashMap, future::Future, sync::RwLock};
+
+use gloo_storage::{LocalStorage, Storage as _};
+
+use crate::utils::json_db::DataError;
+
+/// Status and body of a GET request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+pub trait HttpClient {
+    /// GETs `url`, sending `headers` along.
+    fn get(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+    ) -> impl Future<Output = Result<HttpResponse, DataError>>;
+}
+
+/// Strings kept between visits.
+pub trait Storage {
+    fn get(&self, key: &str) -> Option<String>;
+    fn set(&self, key: &str, value: &str) -> Result<(), DataError>;
+    /// Forgets every key set through this storage, and nothing else.
+    fn clear(&self);
+}
+
+/// `reqwest`, which goes through `fetch` in the browser.
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestClient {
+    client: reqwest::Client,
+}
+
+impl HttpClient for ReqwestClient {
+    async fn get(&self, url: &str, headers: &[(&str, &str)]) -> Result<HttpResponse, DataError> {
+        let mut request = self.client.get(url);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let response = request.send().await?;
+        Ok(HttpResponse {
+            status: response.status().as_u16(),
+            body: response.text().await?,
+        })
+    }
+}
+
+/// The browser's `localStorage`. Keys are stored under a prefix of their
+/// own, so clearing them leaves what the rest of the app keeps there, such
+/// as the sidebar's open sections.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BrowserStorage;
+
+impl BrowserStorage {
+    const PREFIX: &'static str = "content:";
+
+    fn key(key: &str) -> String {
+        format!("{}{key}", Self::PREFIX)
+    }
+}
+
+impl Storage for BrowserStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        LocalStorage::get(Self::key(key)).ok()
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), DataError> {
+        Ok(LocalStorage::set(Self::key(key), value)?)
+    }
+
+    fn clear(&self) {
+        let raw = LocalStorage::raw();
+        let keys: Vec<String> = (0..LocalStorage::length())
+            .filter_map(|i| raw.key(i).ok().flatten())
+            .filter(|key| key.starts_with(Self::PREFIX))
+            .collect();
+        for key in keys {
+            LocalStorage::delete(key);
+        }
+    }
+}
+
+/// Storage that lasts as long as the value, for native tools and tests.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    values: RwLock<HashMap<String, String>>,
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.values
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key)
+            .cloned()
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), DataError> {
+        self.values
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn clear(&self) {
+        self.values
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::{
+        collections::HashMap,
+        future::Future,
+        pin::pin,
+        sync::{Arc, RwLock},
+        task::{Context, Poll, Waker},
+    };
+
+    use super::{HttpClient, HttpResponse};
+    use crate::utils::json_db::DataError;
+
+    /// Answers GETs from a fixed set of bodies, 404 for anything else, and
+    /// remembers what was asked for. Clones share their state.
+    #[derive(Clone, Default)]
+    pub struct StubHttp {
+        bodies: Arc<RwLock<HashMap<String, String>>>,
+        requests: Arc<RwLock<Vec<String>>>,
+    }
+
+    impl StubHttp {
+        pub fn serve(&self, url: &str, body: &str) -> &Self {
+            self.bodies
+                .write()
+                .unwrap()
+                .insert(url.to_string(), body.to_string());
+            self
+        }
+
+        /// URLs requested so far, forgetting them.
+        pub fn take_requests(&self) -> Vec<String> {
+            std::mem::take(&mut *self.requests.write().unwrap())
+        }
+    }
+
+    impl HttpClient for StubHttp {
+        async fn get(&self, url: &str, _headers: &[(&str, &str)]) -> Result<HttpResponse, DataError> {
+            self.requests.write().unwrap().push(url.to_string());
+            Ok(match self.bodies.read().unwrap().get(url) {
+                Some(body) => HttpResponse { status: 200, body: body.clone() },
+                None => HttpResponse { status: 404, body: String::new() },
+            })
+        }
+    }
+
+    /// Runs a future that never waits on anything outside itself, as the
+    /// loader does with the stub backends. Nothing would ever wake it up
+    /// again, so a future that does wait fails the test instead of hanging.
+    pub fn block_on<F: Future>(future: F) -> F::Output {
+        let mut cx = Context::from_waker(Waker::noop());
+        match pin!(future).poll(&mut cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future waited on something the test backends never provide"),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+
+/// A single front matter value: either `key: value` or a list written as
+/// `key: [a, b]` or as `- item` lines below the key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrontValue {
+    Scalar(String),
+    List(Vec<String>),
+}
+
+/// The `---` delimited block at the top of a markdown page. Only the flat
+/// subset of YAML used by content authors is understood.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrontMatter {
+    values: BTreeMap<String, FrontValue>,
+}
+
+impl FrontMatter {
+    pub fn get_str(&self, key: &str) -> Option<&str> {
+        match self.values.get(key)? {
+            FrontValue::Scalar(value) => Some(value.as_str()),
+            FrontValue::List(_) => None,
+        }
+    }
+
+    pub fn get_list(&self, key: &str) -> Vec<String> {
+        match self.values.get(key) {
+            Some(FrontValue::List(items)) => items.clone(),
+            Some(FrontValue::Scalar(value)) if !value.is_empty() => vec![value.clone()],
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get_str(key)?.to_lowercase().as_str() {
+            "true" | "yes" | "on" => Some(true),
+            "false" | "no" | "off" => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn get_i32(&self, key: &str) -> Option<i32> {
+        self.get_str(key)?.parse().ok()
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.get_str("title")
+    }
+
+    pub fn aliases(&self) -> Vec<String> {
+        self.get_list("aliases")
+    }
+}
+
+/// Splits the front matter off `markdown`, returning it together with the
+/// remaining body. Pages without front matter get an empty one.
+pub fn split_front_matter(markdown: &str) -> (FrontMatter, &str) {
+    let text = markdown.strip_prefix('\u{feff}').unwrap_or(markdown);
+    let Some(rest) = text
+        .strip_prefix("---\n")
+        .or_else(|| text.strip_prefix("---\r\n"))
+    else {
+        return (FrontMatter::default(), markdown);
+    };
+
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            let block = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return (parse_block(block), body);
+        }
+        offset += line.len();
+    }
+
+    // An unterminated block is ordinary markdown (a leading thematic break)
+    (FrontMatter::default(), markdown)
+}
+
+fn parse_block(block: &str) -> FrontMatter {
+    let mut values = BTreeMap::new();
+    let mut list_key: Option<String> = None;
+
+    for line in block.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if let Some(item) = trimmed.strip_prefix("- ") {
+            if let Some(key) = &list_key {
+                if let Some(FrontValue::List(items)) = values.get_mut(key) {
+                    items.push(unquote(item));
+                }
+            }
+            continue;
+        }
+
+        let Some((key, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_string();
+        let value = value.trim();
+
+        if value.is_empty() {
+            values.insert(key.clone(), FrontValue::List(Vec::new()));
+            list_key = Some(key);
+        } else if let Some(inner) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            let items = inner
+                .split(',')
+                .map(unquote)
+                .filter(|item| !item.is_empty())
+                .collect();
+            values.insert(key, FrontValue::List(items));
+            list_key = None;
+        } else {
+            values.insert(key, FrontValue::Scalar(unquote(value)));
+            list_key = None;
+        }
+    }
+
+    FrontMatter { values }
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+        .to_string()
+}
@@ -0,0 +1,115 @@
+/// Number of single-character edits needed to turn `a` into `b`.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| c == '/' || c == '-' || c == '_' || c.is_whitespace())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Scores how far a page is from `query`; lower is closer. Every query word
+/// is matched against the closest path segment or title word.
+fn distance(query: &[String], name: &str, path: &str) -> usize {
+    let mut candidates = words(path);
+    candidates.extend(words(name));
+
+    query
+        .iter()
+        .map(|word| {
+            candidates
+                .iter()
+                .map(|candidate| {
+                    if candidate.starts_with(word.as_str()) {
+                        0
+                    } else {
+                        levenshtein(word, candidate)
+                    }
+                })
+                .min()
+                .unwrap_or(word.len())
+        })
+        .sum()
+}
+
+/// Returns up to `limit` `(name, path)` pages resembling `query`, closest first.
+pub fn suggest<'a>(query: &str, pages: &'a [(String, String)], limit: usize) -> Vec<&'a (String, String)> {
+    let query = words(query);
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let budget = (query.iter().map(String::len).sum::<usize>() / 3).max(2);
+    let mut ranked: Vec<(usize, &(String, String))> = pages
+        .iter()
+        .map(|page| (distance(&query, &page.0, &page.1), page))
+        .filter(|(score, _)| *score <= budget)
+        .collect();
+
+    ranked.sort_by(|(a, page_a), (b, page_b)| a.cmp(b).then_with(|| page_a.1.cmp(&page_b.1)));
+    ranked.into_iter().take(limit).map(|(_, page)| page).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages() -> Vec<(String, String)> {
+        [
+            ("Getting Started", "/pages/guide/getting-started"),
+            ("Installation", "/pages/guide/install"),
+            ("Release Notes", "/pages/releases"),
+            ("Home", "/pages/home"),
+        ]
+        .into_iter()
+        .map(|(name, path)| (name.to_string(), path.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn levenshtein_counts_edits() {
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("guide", "guide"), 0);
+        assert_eq!(levenshtein("héllo", "hello"), 1);
+    }
+
+    #[test]
+    fn typos_and_prefixes_find_their_page() {
+        let pages = pages();
+        let best = |query| suggest(query, &pages, 8).first().map(|page| page.1.as_str());
+        assert_eq!(best("pages/guide/instal"), Some("/pages/guide/install"));
+        assert_eq!(best("relaese"), Some("/pages/releases"));
+        assert_eq!(best("Getting Started"), Some("/pages/guide/getting-started"));
+    }
+
+    #[test]
+    fn far_queries_suggest_nothing() {
+        let pages = pages();
+        assert!(suggest("", &pages, 8).is_empty());
+        assert!(suggest("/-_ ", &pages, 8).is_empty());
+        assert!(suggest("zzzzzzzz", &pages, 8).is_empty());
+    }
+
+    #[test]
+    fn ties_are_ordered_by_path_and_limited() {
+        let pages = pages();
+        let paths: Vec<_> = suggest("pages", &pages, 2).into_iter().map(|page| page.1.as_str()).collect();
+        assert_eq!(paths, ["/pages/guide/getting-started", "/pages/guide/install"]);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::utils::front_matter::FrontMatter;
+
+/// The site name, as in the `title` of `Dioxus.toml`.
+pub const SITE_NAME: &str = "blogy";
+/// Longest description taken from a page's opening paragraph, in characters.
+const DESCRIPTION_LEN: usize = 160;
+
+/// What a page says about itself in the document head.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageMeta {
+    pub title: String,
+    pub description: Option<String>,
+    /// Absolute URL the page is published at.
+    pub canonical: Option<String>,
+    /// Absolute URL of the image shown when the page is shared.
+    pub image: Option<String>,
+}
+
+/// A tag of the document head other than `<title>`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeadTag {
+    /// `<meta name=…>`
+    Name { name: &'static str, content: String },
+    /// `<meta property=…>`, as used by Open Graph.
+    Property { property: &'static str, content: String },
+    /// `<link rel="canonical">`
+    Canonical(String),
+}
+
+impl PageMeta {
+    /// Meta for a page without content of its own, such as an error page.
+    pub fn titled(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Front matter `title:`, `description:` and `image:` win over the name
+    /// from the index and the page's opening paragraph.
+    pub fn from_page(
+        name: &str,
+        front_matter: &FrontMatter,
+        excerpt: &str,
+        canonical: Option<String>,
+        image_url: impl Fn(&str) -> String,
+    ) -> Self {
+        let description = front_matter
+            .get_str("description")
+            .map(str::to_string)
+            .or_else(|| Some(shorten(excerpt)).filter(|text| !text.is_empty()));
+
+        Self {
+            title: front_matter.title().unwrap_or(name).to_string(),
+            description,
+            canonical,
+            image: front_matter.get_str("image").map(image_url),
+        }
+    }
+
+    pub fn document_title(&self) -> String {
+        if self.title.is_empty() || self.title == SITE_NAME {
+            SITE_NAME.to_string()
+        } else {
+            format!("{} · {SITE_NAME}", self.title)
+        }
+    }
+
+    pub fn tags(&self) -> Vec<HeadTag> {
+        let name = |name, content: &str| HeadTag::Name { name, content: content.to_string() };
+        let property = |property, content: &str| HeadTag::Property {
+            property,
+            content: content.to_string(),
+        };
+
+        let mut tags = vec![
+            property("og:site_name", SITE_NAME),
+            property("og:type", "article"),
+            property("og:title", &self.title),
+            name("twitter:title", &self.title),
+            name(
+                "twitter:card",
+                if self.image.is_some() { "summary_large_image" } else { "summary" },
+            ),
+        ];
+        if let Some(description) = &self.description {
+            tags.push(name("description", description));
+            tags.push(property("og:description", description));
+            tags.push(name("twitter:description", description));
+        }
+        if let Some(url) = &self.canonical {
+            tags.push(HeadTag::Canonical(url.clone()));
+            tags.push(property("og:url", url));
+        }
+        if let Some(image) = &self.image {
+            tags.push(property("og:image", image));
+            tags.push(name("twitter:image", image));
+        }
+        tags
+    }
+
+    /// The same tags as markup, for pages rendered ahead of time.
+    #[cfg(feature = "cli")]
+    pub fn head_html(&self) -> String {
+        let mut html = format!("<title>{}</title>", escape(&self.document_title()));
+        for tag in self.tags() {
+            html.push('\n');
+            html.push_str(&match tag {
+                HeadTag::Name { name, content } => {
+                    format!(r#"<meta name="{name}" content="{}">"#, escape(&content))
+                }
+                HeadTag::Property { property, content } => {
+                    format!(r#"<meta property="{property}" content="{}">"#, escape(&content))
+                }
+                HeadTag::Canonical(url) => format!(r#"<link rel="canonical" href="{}">"#, escape(&url)),
+            });
+        }
+        html
+    }
+}
+
+/// Cuts `text` down to a description, at a word boundary.
+fn shorten(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= DESCRIPTION_LEN {
+        return text;
+    }
+
+    let cut: String = text.chars().take(DESCRIPTION_LEN).collect();
+    let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(head, _)| head);
+    format!("{}…", cut.trim_end_matches(|c: char| c.is_ascii_punctuation()))
+}
+
+#[cfg(feature = "cli")]
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
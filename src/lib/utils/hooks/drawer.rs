@@ -0,0 +1,93 @@
+use std::rc::Rc;
+
+use dioxus::prelude::*;
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+use crate::Route;
+
+const SCROLL_LOCK_ATTR: &str = "data-scroll-locked";
+
+fn set_scroll_locked(locked: bool) {
+    let Some(body) = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.body())
+    else {
+        return;
+    };
+    if locked {
+        let _ = body.set_attribute(SCROLL_LOCK_ATTR, "");
+    } else {
+        let _ = body.remove_attribute(SCROLL_LOCK_ATTR);
+    }
+}
+
+/// Keeps the page behind an overlay from scrolling while `locked` is set.
+pub fn use_body_scroll_lock(locked: Signal<bool>) {
+    use_effect(move || set_scroll_locked(locked()));
+    use_drop(|| set_scroll_locked(false));
+}
+
+/// Lets the browser's back button close an overlay. Opening it pushes a
+/// history entry for the current URL, going back pops that entry and closes
+/// the overlay, and closing it any other way steps back over the entry.
+///
+/// The returned callback follows a link out of the overlay and closes it. The
+/// route takes the place of the overlay's entry, so going back from it lands
+/// where the overlay was opened.
+pub fn use_close_on_back(mut open: Signal<bool>) -> Callback<Route> {
+    let mut entry_pushed = use_signal(|| false);
+    let navigator = use_navigator();
+
+    let listener = use_hook(move || {
+        let mut open = open.to_owned();
+
+        let closure = Closure::wrap(Box::new(move || {
+            if *entry_pushed.peek() {
+                entry_pushed.set(false);
+                open.set(false);
+            }
+        }) as Box<dyn FnMut()>);
+
+        if let Some(window) = web_sys::window() {
+            let _ = window
+                .add_event_listener_with_callback("popstate", closure.as_ref().unchecked_ref());
+        }
+
+        Rc::new(closure)
+    });
+
+    use_drop(move || {
+        if let Some(window) = web_sys::window() {
+            let _ = window.remove_event_listener_with_callback(
+                "popstate",
+                listener.as_ref().as_ref().unchecked_ref(),
+            );
+        }
+    });
+
+    use_effect(move || {
+        let is_open = open();
+        let Some(history) = web_sys::window().and_then(|window| window.history().ok()) else {
+            return;
+        };
+
+        let pushed = *entry_pushed.peek();
+        if is_open && !pushed {
+            if history.push_state(&JsValue::NULL, "").is_ok() {
+                entry_pushed.set(true);
+            }
+        } else if !is_open && pushed {
+            entry_pushed.set(false);
+            let _ = history.back();
+        }
+    });
+
+    use_callback(move |route: Route| {
+        if entry_pushed.take() {
+            navigator.replace(route);
+        } else {
+            navigator.push(route);
+        }
+        open.set(false);
+    })
+}
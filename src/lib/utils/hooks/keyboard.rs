@@ -0,0 +1,53 @@
+use dioxus::prelude::*;
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::{Document, HtmlElement, KeyboardEvent};
+
+fn is_typing(document: &Document) -> bool {
+    document.active_element().is_some_and(|element| {
+        matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+            || element
+                .dyn_ref::<HtmlElement>()
+                .is_some_and(|element| element.is_content_editable())
+    })
+}
+
+/// Follows the current page's next/previous link when `j`/`k` is pressed.
+/// Mount once, near the root, so the listener is not registered per page.
+pub fn use_page_nav_shortcuts() {
+    use_hook(|| {
+        let closure = Closure::wrap(Box::new(move |evt: KeyboardEvent| {
+            if evt.ctrl_key() || evt.meta_key() || evt.alt_key() || evt.default_prevented() {
+                return;
+            }
+
+            let selector = match evt.key().as_str() {
+                "j" => r#".page-nav a[rel~="next"]"#,
+                "k" => r#".page-nav a[rel~="prev"]"#,
+                _ => return,
+            };
+
+            let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+                return;
+            };
+
+            if is_typing(&document) {
+                return;
+            }
+
+            // Clicking the rendered link lets the router handle the navigation
+            if let Ok(Some(link)) = document.query_selector(selector) {
+                if let Ok(link) = link.dyn_into::<HtmlElement>() {
+                    evt.prevent_default();
+                    link.click();
+                }
+            }
+        }) as Box<dyn FnMut(_)>);
+
+        if let Some(document) = web_sys::window().and_then(|window| window.document()) {
+            let _ = document
+                .add_event_listener_with_callback("keydown", closure.as_ref().unchecked_ref());
+        }
+
+        closure.forget();
+    });
+}
@@ -0,0 +1,33 @@
+use dioxus::prelude::*;
+use wasm_bindgen::{closure::Closure, JsCast};
+use web_sys::EventSource;
+
+use crate::DEV_SERVER;
+
+/// Counts the content changes reported by `blogy-serve`. Stays at zero when
+/// the app was not built against a dev server.
+pub fn use_live_reload() -> Signal<u64> {
+    let changes = use_signal(|| 0u64);
+
+    let source = use_hook(move || {
+        let mut changes = changes.to_owned();
+        let server = DEV_SERVER?;
+        let source = EventSource::new(&format!("{server}/events")).ok()?;
+
+        let closure = Closure::wrap(Box::new(move || {
+            changes += 1;
+        }) as Box<dyn FnMut()>);
+        source.set_onmessage(Some(closure.as_ref().unchecked_ref()));
+        closure.forget();
+
+        Some(source)
+    });
+
+    use_drop(move || {
+        if let Some(source) = &source {
+            source.close();
+        }
+    });
+
+    changes
+}
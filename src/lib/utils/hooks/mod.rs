@@ -1,2 +1,5 @@
+pub mod drawer;
+pub mod keyboard;
+pub mod live_reload;
 pub mod outside_hook;
 pub mod scroll_header;
@@ -0,0 +1,296 @@
+use std::{collections::BTreeMap, ops::Deref};
+
+use dioxus::logger::tracing;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::utils::json_db::DataError;
+
+/// Latest `index.json` format understood by this build. Files without a
+/// `version` field predate versioning and are read as version 1.
+pub const INDEX_VERSION: u32 = 1;
+
+/// The whole index, generic over its root so that `parse_index` can read
+/// the file before its nodes. Only `IndexFile<IndexNode>`, the parsed form,
+/// is meant to be used outside this module.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexFile<R = IndexNode> {
+    #[serde(default = "legacy_version")]
+    pub version: u32,
+    pub root: R,
+    #[serde(default, skip_serializing_if = "SiteConfig::is_empty")]
+    pub site: SiteConfig,
+    /// Old paths mapped to the path readers should be sent to instead.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub redirects: BTreeMap<String, String>,
+}
+
+/// Site-wide settings carried by the index.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SiteConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<Layout>,
+}
+
+impl SiteConfig {
+    fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// How pages are framed: the blog layout navigates through the header menu
+/// only, the docs layout adds a persistent sidebar tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    #[default]
+    Blog,
+    Docs,
+}
+
+/// A node of the index, generic over what a directory keeps its children
+/// in: `parse_index` reads each node with its children still raw JSON, so
+/// that a child it cannot use is skipped or reported on its own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+#[serde(bound(deserialize = "C: Deserialize<'de> + Default"))]
+pub enum Node<C> {
+    Page {
+        name: String,
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        aliases: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        weight: Option<i32>,
+        /// Left out of the menus but still served at its path.
+        #[serde(default, skip_serializing_if = "is_false")]
+        hidden: bool,
+        /// Shorter label used in the menus instead of `name`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        nav_title: Option<String>,
+    },
+    Directory {
+        name: String,
+        path: String,
+        #[serde(default)]
+        children: C,
+        /// Layout for every page below this directory, overriding the site's.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        layout: Option<Layout>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        weight: Option<i32>,
+        #[serde(default, skip_serializing_if = "is_false")]
+        hidden: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        nav_title: Option<String>,
+    },
+    /// A menu entry pointing at a URL rather than a page of the index.
+    Link {
+        name: String,
+        url: String,
+        /// Opens in a new tab; defaults to whether `url` is absolute.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        external: Option<bool>,
+    },
+    /// A divider between groups of menu entries.
+    Separator,
+    /// A non-clickable label introducing the entries below it.
+    Heading { name: String },
+}
+
+/// A node of a parsed index.
+pub type IndexNode = Node<Children>;
+
+/// The nodes below a directory of a parsed index. Not deserializable, so
+/// that an index is only ever read through `parse_index`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct Children(pub Vec<IndexNode>);
+
+impl Deref for Children {
+    type Target = Vec<IndexNode>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Vec<IndexNode>> for Children {
+    fn from(nodes: Vec<IndexNode>) -> Self {
+        Self(nodes)
+    }
+}
+
+impl<'a> IntoIterator for &'a Children {
+    type Item = &'a IndexNode;
+    type IntoIter = std::slice::Iter<'a, IndexNode>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+fn legacy_version() -> u32 {
+    1
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+/// The `type` of every node this build can read.
+const NODE_KINDS: [&str; 5] = ["page", "directory", "link", "separator", "heading"];
+
+/// Parses `index.json`, skipping node kinds this build does not know about
+/// and reporting the JSON path of any node that cannot be read.
+pub fn parse_index(json: &str) -> Result<IndexFile, DataError> {
+    let raw: IndexFile<Value> = serde_json::from_str(json)?;
+    if raw.version > INDEX_VERSION {
+        return Err(DataError::UnsupportedVersion(raw.version));
+    }
+
+    let root = parse_node(&raw.root, "$.root")?.ok_or_else(|| DataError::InvalidNode {
+        path: "$.root".into(),
+        message: "root node has an unsupported type".into(),
+    })?;
+
+    Ok(IndexFile {
+        version: raw.version,
+        root,
+        site: raw.site,
+        redirects: raw.redirects,
+    })
+}
+
+fn parse_node(value: &Value, json_path: &str) -> Result<Option<IndexNode>, DataError> {
+    let invalid = |message: String| DataError::InvalidNode {
+        path: json_path.to_string(),
+        message,
+    };
+
+    let kind = value
+        .get("type")
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid("missing string field `type`".into()))?;
+    if !NODE_KINDS.contains(&kind) {
+        tracing::warn!("Skipping index node {json_path} with unknown type `{kind}`");
+        return Ok(None);
+    }
+
+    let node: Node<Vec<Value>> =
+        serde_json::from_value(value.clone()).map_err(|e| invalid(e.to_string()))?;
+    Ok(Some(match node {
+        Node::Page { name, path, file, aliases, weight, hidden, nav_title } => {
+            Node::Page { name, path, file, aliases, weight, hidden, nav_title }
+        }
+        Node::Directory { name, path, children: raw_children, layout, weight, hidden, nav_title } => {
+            let mut children = Vec::with_capacity(raw_children.len());
+            for (i, child) in raw_children.iter().enumerate() {
+                let child_path = format!("{json_path}.children[{i}]");
+                if let Some(node) = parse_node(child, &child_path)? {
+                    children.push(node);
+                }
+            }
+            Node::Directory { name, path, children: children.into(), layout, weight, hidden, nav_title }
+        }
+        Node::Link { name, url, external } => Node::Link { name, url, external },
+        Node::Separator => Node::Separator,
+        Node::Heading { name } => Node::Heading { name },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_node_kind_is_read() {
+        let index = parse_index(
+            r#"{"version": 1, "site": {"layout": "docs"}, "redirects": {"/old": "/pages/home"},
+                "root": {"type": "directory", "name": "Pages", "path": "/pages", "children": [
+                    {"type": "page", "name": "Home", "path": "/pages/home", "aliases": ["/start"], "weight": 2},
+                    {"type": "link", "name": "Source", "url": "https://example.test"},
+                    {"type": "separator"},
+                    {"type": "heading", "name": "More"}
+                ]}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(index.site.layout, Some(Layout::Docs));
+        assert_eq!(index.redirects["/old"], "/pages/home");
+        let IndexNode::Directory { children, .. } = &index.root else {
+            panic!("root is not a directory: {:?}", index.root);
+        };
+        assert_eq!(children.len(), 4);
+        assert!(matches!(&children[0], IndexNode::Page { aliases, weight: Some(2), hidden: false, .. } if aliases == &["/start"]));
+        assert!(matches!(&children[1], IndexNode::Link { external: None, .. }));
+        assert_eq!(children[2], IndexNode::Separator);
+        assert_eq!(children[3], IndexNode::Heading { name: "More".into() });
+    }
+
+    #[test]
+    fn written_indexes_read_back_the_same() {
+        let index = parse_index(
+            r#"{"version": 1, "redirects": {"/old": "/pages/home"},
+                "root": {"type": "directory", "name": "Pages", "path": "/pages", "layout": "docs", "children": [
+                    {"type": "page", "name": "Home", "path": "/pages/home", "file": "home.md", "hidden": true},
+                    {"type": "link", "name": "Source", "url": "https://example.test", "external": false},
+                    {"type": "heading", "name": "More"}
+                ]}}"#,
+        )
+        .unwrap();
+
+        let written = serde_json::to_string(&index).unwrap();
+        assert_eq!(parse_index(&written).unwrap(), index);
+    }
+
+    #[test]
+    fn unversioned_files_are_version_one_and_newer_ones_are_refused() {
+        let root = r#""root": {"type": "page", "name": "Home", "path": "/pages/home"}"#;
+        assert_eq!(parse_index(&format!("{{{root}}}")).unwrap().version, 1);
+
+        let newer = format!(r#"{{"version": {}, {root}}}"#, INDEX_VERSION + 1);
+        assert!(matches!(parse_index(&newer), Err(DataError::UnsupportedVersion(v)) if v == INDEX_VERSION + 1));
+    }
+
+    #[test]
+    fn unknown_kinds_are_skipped() {
+        let index = parse_index(
+            r#"{"root": {"type": "directory", "name": "Pages", "path": "/pages", "children": [
+                {"type": "carousel", "slides": 3},
+                {"type": "page", "name": "Home", "path": "/pages/home"}
+            ]}}"#,
+        )
+        .unwrap();
+        let IndexNode::Directory { children, .. } = &index.root else {
+            panic!("root is not a directory: {:?}", index.root);
+        };
+        assert!(matches!(children.as_slice(), [IndexNode::Page { name, .. }] if name == "Home"));
+
+        let unknown_root = parse_index(r#"{"root": {"type": "carousel"}}"#);
+        assert!(matches!(unknown_root, Err(DataError::InvalidNode { path, .. }) if path == "$.root"));
+    }
+
+    #[test]
+    fn broken_nodes_are_reported_by_json_path() {
+        let error = parse_index(
+            r#"{"root": {"type": "directory", "name": "Pages", "path": "/pages", "children": [
+                {"type": "page", "name": "Home", "path": "/pages/home"},
+                {"type": "directory", "name": "Guide", "path": "/pages/guide", "children": [
+                    {"type": "page", "name": "Intro"}
+                ]}
+            ]}}"#,
+        )
+        .unwrap_err();
+        let DataError::InvalidNode { path, message } = error else {
+            panic!("unexpected error: {error}");
+        };
+        assert_eq!(path, "$.root.children[1].children[0]");
+        assert!(message.contains("path"), "{message}");
+
+        let untyped = parse_index(r#"{"root": {"name": "Pages"}}"#).unwrap_err();
+        assert!(untyped.to_string().contains("missing string field `type`"), "{untyped}");
+    }
+}
@@ -1,163 +1,443 @@
 use std::collections::HashMap;
-use reqwest::{header::{HeaderMap, HeaderValue, ACCEPT}, StatusCode};
-use serde_json::Value;
-use gloo_storage::{LocalStorage, Storage};
-use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag, TagEnd};
-use syntect::html::highlighted_html_for_string;
-use crate::{BASE_URL, SYNTAX_SET, THEME_SET};
+use std::sync::Arc;
+use crate::{BASE_URL, COMMITS_URL, DEV_SERVER};
+use crate::utils::backend::{BrowserStorage, HttpClient, ReqwestClient, Storage};
+use crate::utils::front_matter::{split_front_matter, FrontMatter};
+use crate::utils::index::{parse_index, IndexFile, IndexNode, Layout};
+use crate::utils::nav_tree::trail;
+use crate::utils::page_cache::{page_cache, HtmlKey, PageCache};
+use crate::utils::page_stats::PageStats;
+use crate::utils::plugins::PageInfo;
+use crate::utils::render::{markdown_to_html, RenderContext};
 
 
 #[derive(Debug, Clone)]
 pub struct JsonDb {
-    pages: HashMap<PageKey, PageData>,
+    /// Pages keyed by their normalised route path.
+    pages: HashMap<String, PageData>,
+    /// Normalised alternative paths mapped to the normalised page path they serve.
+    aliases: HashMap<String, String>,
+    /// Normalised old paths mapped to the path the reader is sent to.
+    redirects: HashMap<String, String>,
+    /// Every node of the index in index order, under its own name.
+    entries: Vec<NavNode>,
+    /// Menu placement of nodes, keyed by normalised path.
+    nav_meta: HashMap<String, NavMeta>,
+    /// `entries` in menu order and under their menu labels, hidden nodes included.
+    site_tree: Vec<NavNode>,
+    /// `site_tree` without the hidden nodes, as shown in the menus.
     nav_tree: Vec<NavNode>,
+    /// Layout chosen for the whole site in the index.
+    site_layout: Layout,
+    /// Normalised directory paths whose section asks for its own layout.
+    section_layouts: HashMap<String, Layout>,
+    revision: String,
 }
 
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
-struct PageKey {
+#[derive(Debug, Clone)]
+struct PageData {
     section: String,
     name: String,
+    path: String,
+    file: Option<String>,
 }
 
+/// Where a node goes in the menus, as the index says.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct NavMeta {
+    weight: Option<i32>,
+    hidden: bool,
+    nav_title: Option<String>,
+}
+
+/// Everything needed to fetch and render one page without holding on to the
+/// database lock.
 #[derive(Debug, Clone)]
-struct PageData {
+pub struct PageSource {
     path: String,
-    file: Option<String>,
-    raw_content: Option<String>,
+    /// The markdown file relative to the content root.
+    file: String,
+    storage_key: String,
+    revision: String,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum NavNode {
     Page { name: String, path: String },
     Directory { name: String, path: String, children: Vec<NavNode> },
+    Link { name: String, url: String, external: bool },
+    Separator,
+    Heading { name: String },
 }
 
+impl NavNode {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Page { name, .. }
+            | Self::Directory { name, .. }
+            | Self::Link { name, .. }
+            | Self::Heading { name } => name,
+            Self::Separator => "",
+        }
+    }
 
-impl JsonDb {
-    async fn get_hash() -> Result<String, DataError>{
-        let mut headers = HeaderMap::new();
-            headers.insert(
-                ACCEPT,
-                HeaderValue::from_static("application/vnd.github+json"),
-            );
-
-            let client = reqwest::Client::new();
-            let resp: String = client
-                .get("https://api.github.com/repos/amaali7/markdown_files/commits")
-                .query(&[("sha", "main"), ("per_page", "1")])
-                .headers(headers)
-                .send()
-                .await?
-                .text()
-                .await?;
-            let resp : serde_json::Value = serde_json::from_str(&resp)?;
-            Ok( resp[0]["sha"]
-                .as_str().unwrap().trim().to_string())
-
-    }
-    pub async fn load() -> Result<Self, DataError> {
-
-        if let Ok(local_sha) = LocalStorage::get::<String>("hash") {
-            if Self::get_hash().await? == local_sha.trim() {
-                if let Ok(cached) = LocalStorage::get::<String>("JsonDB") {
-                    Self::from_json(&cached)
-                }else {
-                    Self::update().await
+    /// The route path served by this node; empty for entries that are not
+    /// pages of the site.
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Page { path, .. } | Self::Directory { path, .. } => path,
+            Self::Link { .. } | Self::Separator | Self::Heading { .. } => "",
+        }
+    }
+}
+
+
+/// An index newer than the cached one, fetched but not yet in use.
+pub struct PendingRevision {
+    hash: String,
+    json: String,
+    db: JsonDb,
+}
+
+const HASH_KEY: &str = "hash";
+const INDEX_KEY: &str = "JsonDB";
+
+/// Where the index, the pages and the current revision are fetched from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContentSource {
+    /// Files below `base_url`, at the latest commit reported by the GitHub
+    /// commits API at `commits_url`.
+    GitHub { base_url: String, commits_url: String },
+    /// A `blogy-serve` instance, which reports its own revision.
+    DevServer { url: String },
+}
+
+impl ContentSource {
+    /// The dev server when the app was built against one, GitHub otherwise.
+    pub fn configured() -> Self {
+        match DEV_SERVER {
+            Some(url) => Self::DevServer { url: url.to_string() },
+            None => Self::GitHub {
+                base_url: BASE_URL.to_string(),
+                commits_url: COMMITS_URL.to_string(),
+            },
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        match self {
+            Self::GitHub { base_url, .. } => base_url,
+            Self::DevServer { url } => url,
+        }
+    }
+}
+
+/// Runs the database lifecycle: loading the index, noticing new revisions
+/// and fetching pages, with the index and pages kept in `storage` and the
+/// pages read so far in `cache`.
+pub struct ContentLoader<H, S> {
+    http: H,
+    storage: S,
+    source: ContentSource,
+    cache: Arc<PageCache>,
+}
+
+impl ContentLoader<ReqwestClient, BrowserStorage> {
+    /// The loader the app uses: `fetch`, `localStorage`, the configured
+    /// source and the page cache shared by every view.
+    pub fn browser() -> Self {
+        Self::new(ReqwestClient::default(), BrowserStorage, ContentSource::configured())
+            .with_cache(page_cache())
+    }
+}
+
+impl<H: HttpClient, S: Storage> ContentLoader<H, S> {
+    /// A loader with a page cache of its own.
+    pub fn new(http: H, storage: S, source: ContentSource) -> Self {
+        Self {
+            http,
+            storage,
+            source,
+            cache: Arc::default(),
+        }
+    }
+
+    /// Shares `cache` with other loaders.
+    pub fn with_cache(mut self, cache: Arc<PageCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    pub fn cache(&self) -> &Arc<PageCache> {
+        &self.cache
+    }
+
+    async fn get_hash(&self) -> Result<String, DataError> {
+        match &self.source {
+            ContentSource::DevServer { url } => {
+                let response = self.http.get(&format!("{url}/revision"), &[]).await?;
+                Ok(response.body.trim().to_string())
+            }
+            ContentSource::GitHub { commits_url, .. } => {
+                let response = self
+                    .http
+                    .get(commits_url, &[("Accept", "application/vnd.github+json")])
+                    .await?;
+                let resp: serde_json::Value = serde_json::from_str(&response.body)?;
+                resp[0]["sha"]
+                    .as_str()
+                    .map(|sha| sha.trim().to_string())
+                    .ok_or(DataError::InvalidStructure)
+            }
+        }
+    }
+
+    pub async fn load(&self) -> Result<JsonDb, DataError> {
+        let remote_sha = self.get_hash().await?;
+
+        if let Some(local_sha) = self.storage.get(HASH_KEY) {
+            if remote_sha == local_sha.trim() {
+                if let Some(cached) = self.storage.get(INDEX_KEY) {
+                    return self.parse_db(&cached, &remote_sha);
                 }
-            }else {
-                LocalStorage::clear();
-                let _ = LocalStorage::set("hash", Self::get_hash().await?);
-                Self::update().await
+                return self.update(&remote_sha).await;
             }
-        } else {
-            LocalStorage::clear();
-            let _ = LocalStorage::set("hash", Self::get_hash().await?);
-            Self::update().await
         }
+
+        self.storage.clear();
+        let _ = self.storage.set(HASH_KEY, &remote_sha);
+        self.update(&remote_sha).await
+    }
+
+    /// Builds the database from the index cached by a previous visit, without
+    /// touching the network.
+    pub fn load_cached(&self) -> Option<JsonDb> {
+        let local_sha = self.storage.get(HASH_KEY)?;
+        let cached = self.storage.get(INDEX_KEY)?;
+        self.parse_db(&cached, local_sha.trim()).ok()
+    }
+
+    /// Compares the cached revision with the latest one and, when they
+    /// differ, fetches the new index without replacing the cached one yet.
+    pub async fn check_revision(&self) -> Result<Option<PendingRevision>, DataError> {
+        let remote_sha = self.get_hash().await?;
+        let local_sha = self.storage.get(HASH_KEY);
+
+        if local_sha.as_deref().map(str::trim) == Some(remote_sha.as_str()) {
+            return Ok(None);
+        }
+
+        let json = self.fetch_index().await?;
+        let db = self.parse_db(&json, &remote_sha)?;
+        Ok(Some(PendingRevision { hash: remote_sha, json, db }))
+    }
+
+    /// Persists the fetched index and hands back the database built from it.
+    pub fn apply(&self, revision: PendingRevision) -> JsonDb {
+        self.storage.clear();
+        let _ = self.storage.set(HASH_KEY, &revision.hash);
+        let _ = self.storage.set(INDEX_KEY, &revision.json);
+        revision.db
+    }
+
+    async fn fetch_index(&self) -> Result<String, DataError> {
+        let url = format!("{}/index.json", self.source.base_url());
+        Ok(self.http.get(&url, &[]).await?.body)
     }
 
+    async fn update(&self, revision: &str) -> Result<JsonDb, DataError> {
+        let json = self.fetch_index().await?;
+        let _ = self.storage.set(INDEX_KEY, &json);
+        self.parse_db(&json, revision)
+    }
+
+    fn parse_db(&self, json: &str, revision: &str) -> Result<JsonDb, DataError> {
+        let index = parse_index(json).inspect_err(|_| self.storage.clear())?;
+        Ok(JsonDb::from_index(&index, revision))
+    }
+
+    pub async fn render(
+        &self,
+        source: &PageSource,
+        ctx: &RenderContext,
+    ) -> Result<RenderedPage, DataError> {
+        let markdown = self.fetch_page(source).await?;
+        let (front_matter, body) = split_front_matter(&markdown);
+        let key = HtmlKey::new(&source.path, &source.revision, &markdown, ctx);
+
+        let rendered = match self.cache.html(&key) {
+            Some(rendered) => rendered,
+            None => {
+                ctx.syntaxes().ensure_for_markdown(&self.http, body).await;
+                let page = PageInfo { path: &source.path, front_matter: &front_matter };
+                let rendered = markdown_to_html(body, &page, ctx);
+                self.cache.insert_html(key, rendered.clone());
+                rendered
+            }
+        };
 
-    async fn update() -> Result<Self, DataError> {
-        let url = format!("{}/index.json", BASE_URL);
-        let json = reqwest::get(url)
-            .await?
-            .text()
-            .await?;
-        let _ = LocalStorage::set("JsonDB", &json);
-        Self::from_json(&json)
+        Ok(RenderedPage {
+            path: source.path.clone(),
+            html: rendered.html,
+            front_matter,
+            stats: rendered.stats,
+            excerpt: rendered.excerpt,
+        })
     }
 
+    /// The page's markdown, from memory, storage or the network in that
+    /// order. Empty when the source has no such file.
+    pub async fn fetch_page(&self, source: &PageSource) -> Result<String, DataError> {
+        if let Some(markdown) = self.cache.raw(&source.path, &source.revision) {
+            return Ok(markdown);
+        }
+
+        let content = self.load_content_from_source(source).await?;
+        if !content.is_empty() {
+            self.cache.insert_raw(&source.path, &source.revision, content.clone());
+        }
+        Ok(content)
+    }
 
-    fn from_json(json: &str) -> Result<Self, DataError> {
-        let value: Value = serde_json::from_str(json).map_err(|e| {
-            LocalStorage::clear();
-            e
-        })?;
+    async fn load_content_from_source(&self, source: &PageSource) -> Result<String, DataError> {
+        // 1. Check local storage first
+        if let Some(cached) = self.storage.get(&source.storage_key) {
+            return Ok(cached);
+        }
+
+        // 2. Download from network
+        let url = format!("{}{}", self.source.base_url(), source.file);
+        let resp = self.http.get(&url, &[]).await?;
+
+        let content = match resp.status {
+            200 => resp.body,
+            _ => {
+                "".to_string()
+            }
+        };
+        if !content.is_empty() {
+            self.storage.set(&source.storage_key, &content)?;
+        }
+
+        Ok(content)
+    }
+}
+
+impl JsonDb {
+    /// Builds the database from an index that has already been parsed,
+    /// without touching browser storage.
+    pub fn from_index(index: &IndexFile, revision: &str) -> Self {
         let mut db = Self {
             pages: HashMap::new(),
+            aliases: HashMap::new(),
+            redirects: HashMap::new(),
+            entries: Vec::new(),
+            nav_meta: HashMap::new(),
+            site_tree: Vec::new(),
             nav_tree: Vec::new(),
+            site_layout: Layout::default(),
+            section_layouts: HashMap::new(),
+            revision: revision.to_string(),
         };
-        db.build_cache(&value)?;
-        Ok(db)
+        db.build_cache(index);
+        db
     }
 
 
-fn build_cache(&mut self, value: &Value) -> Result<(), DataError> {
-    if let Some(root) = value.get("root") {
-        let mut nav_tree = Vec::new();
-        Self::process_node_static(
-            root,
-            "", // Start with empty path for root
-            &mut nav_tree,
-            &mut self.pages
-        )?;
-        self.nav_tree = nav_tree;
+fn build_cache(&mut self, index: &IndexFile) {
+    let mut entries = Vec::new();
+    Self::process_node_static(
+        &index.root,
+        "", // Start with empty path for root
+        &mut entries,
+        &mut self.pages,
+        &mut self.aliases,
+        &mut self.redirects,
+        &mut self.nav_meta,
+    );
+    self.entries = entries;
+    self.arrange_nav();
+    for (from, to) in &index.redirects {
+        self.redirects.insert(normalize_path(from), to.clone());
     }
-    Ok(())
+    self.site_layout = index.site.layout.unwrap_or_default();
+    Self::collect_layouts(&index.root, &mut self.section_layouts);
 }
 
+    fn collect_layouts(node: &IndexNode, layouts: &mut HashMap<String, Layout>) {
+        if let IndexNode::Directory { path, children, layout, .. } = node {
+            if let Some(layout) = layout {
+                layouts.insert(normalize_path(path), *layout);
+            }
+            for child in children {
+                Self::collect_layouts(child, layouts);
+            }
+        }
+    }
+
     fn process_node_static(
-    node: &Value,
+    node: &IndexNode,
     current_path: &str, // Changed from current_section to current_path
     nav_nodes: &mut Vec<NavNode>,
-    pages: &mut HashMap<PageKey, PageData>,
-) -> Result<(), DataError> {
-    let name = node["name"].as_str().ok_or(DataError::InvalidStructure)?;
-    let path = node["path"].as_str().ok_or(DataError::InvalidStructure)?;
-    let item_type = node["type"].as_str().ok_or(DataError::InvalidStructure)?;
-
-    match item_type {
-        "page" => {
-            let key = PageKey {
-                section: current_path.to_string(), // Use current_path as section
-                name: name.to_string(),
-            };
-
-
+    pages: &mut HashMap<String, PageData>,
+    aliases: &mut HashMap<String, String>,
+    redirects: &mut HashMap<String, String>,
+    nav_meta: &mut HashMap<String, NavMeta>,
+) {
+    match node {
+        IndexNode::Page { name, path, file, aliases: old_paths, weight, hidden, nav_title } => {
             let new_path = if path.strip_suffix(&name.to_lowercase()).is_some() {
-                format!("{}", path)          // path already ends with the name
+                path.to_string()          // path already ends with the name
             } else {
                 format!("{}/{}", path, name.to_lowercase()) // append the name
             };
-            pages.insert(key, PageData {
+            pages.insert(normalize_path(&new_path), PageData {
+                section: current_path.to_string(), // Use current_path as section
+                name: name.to_string(),
                 path: new_path.to_string(),
-                file: node["file"].as_str().map(|s| s.to_string()),
-                raw_content: None,
+                file: file.clone(),
+            });
+            nav_meta.insert(normalize_path(&new_path), NavMeta {
+                weight: *weight,
+                hidden: *hidden,
+                nav_title: nav_title.clone(),
             });
+            for old_path in old_paths {
+                redirects.insert(normalize_path(old_path), new_path.clone());
+            }
             nav_nodes.push(NavNode::Page {
                 name: name.to_string(),
                 path: new_path.to_string(),
             });
         }
-        "directory" => {
+        IndexNode::Directory { name, path, children: child_nodes, weight, hidden, nav_title, .. } => {
             let mut children = Vec::new();
-            if let Some(child_nodes) = node["children"].as_array() {
-                for child in child_nodes {
-                    Self::process_node_static(child, path, &mut children, pages)?;
-                }
+            for child in child_nodes {
+                Self::process_node_static(child, path, &mut children, pages, aliases, redirects, nav_meta);
             }
+            nav_meta.insert(normalize_path(path), NavMeta {
+                weight: *weight,
+                hidden: *hidden,
+                nav_title: nav_title.clone(),
+            });
 
             let dir_name = name.to_lowercase();
+            let landing = children.iter().find_map(|child| match child {
+                NavNode::Page { name: page_name, path: page_path }
+                    if page_name.to_lowercase() == dir_name => Some(normalize_path(page_path)),
+                _ => None,
+            });
+            // Serve the landing page at the directory's own path too
+            if let Some(landing) = landing {
+                let dir_path = normalize_path(path);
+                if dir_path != landing && !pages.contains_key(&dir_path) {
+                    aliases.insert(dir_path, landing);
+                }
+            }
             children.retain(|child| {
                 !matches!(
                     child,
@@ -172,196 +452,251 @@ fn build_cache(&mut self, value: &Value) -> Result<(), DataError> {
                 children,
             });
         }
-        _ => return Err(DataError::InvalidStructure),
+        IndexNode::Link { name, url, external } => {
+            let external = external.unwrap_or_else(|| {
+                url.starts_with("http://") || url.starts_with("https://") || url.starts_with("//")
+            });
+            nav_nodes.push(NavNode::Link {
+                name: name.to_string(),
+                url: url.to_string(),
+                external,
+            });
+        }
+        IndexNode::Separator => nav_nodes.push(NavNode::Separator),
+        IndexNode::Heading { name } => nav_nodes.push(NavNode::Heading { name: name.to_string() }),
     }
-    Ok(())
 }
 
+    /// Rebuilds `site_tree` and `nav_tree` from `entries`: siblings are
+    /// ordered by weight, lightest first, with unweighted nodes after them in
+    /// index order. Links, separators and headings have no weight of their
+    /// own and move with the entry that follows them.
+    fn arrange_nav(&mut self) {
+        fn arrange(nodes: &[NavNode], meta: &HashMap<String, NavMeta>) -> Vec<NavNode> {
+            let meta_of = |node: &NavNode| match node {
+                NavNode::Page { .. } | NavNode::Directory { .. } => {
+                    Some(meta.get(&normalize_path(node.path())).cloned().unwrap_or_default())
+                }
+                _ => None,
+            };
+
+            let mut arranged: Vec<(i32, NavNode)> = nodes
+                .iter()
+                .enumerate()
+                .map(|(i, node)| {
+                    let Some(node_meta) = meta_of(node) else {
+                        let weight = nodes[i..]
+                            .iter()
+                            .chain(nodes[..i].iter().rev())
+                            .find_map(meta_of)
+                            .and_then(|following| following.weight);
+                        return (weight.unwrap_or(i32::MAX), node.clone());
+                    };
+                    let name = node_meta.nav_title.unwrap_or_else(|| node.name().to_string());
+                    let node = match node {
+                        NavNode::Directory { path, children, .. } => NavNode::Directory {
+                            name,
+                            path: path.clone(),
+                            children: arrange(children, meta),
+                        },
+                        _ => NavNode::Page { name, path: node.path().to_string() },
+                    };
+                    (node_meta.weight.unwrap_or(i32::MAX), node)
+                })
+                .collect();
+            arranged.sort_by_key(|(weight, _)| *weight);
+            arranged.into_iter().map(|(_, node)| node).collect()
+        }
+
+        fn visible(nodes: &[NavNode], meta: &HashMap<String, NavMeta>) -> Vec<NavNode> {
+            nodes
+                .iter()
+                .filter(|node| {
+                    node.path().is_empty()
+                        || !meta.get(&normalize_path(node.path())).is_some_and(|m| m.hidden)
+                })
+                .map(|node| match node {
+                    NavNode::Directory { name, path, children } => NavNode::Directory {
+                        name: name.clone(),
+                        path: path.clone(),
+                        children: visible(children, meta),
+                    },
+                    other => other.clone(),
+                })
+                .collect()
+        }
+
+        self.site_tree = arrange(&self.entries, &self.nav_meta);
+        self.nav_tree = visible(&self.site_tree, &self.nav_meta);
+    }
+
     pub fn get_nav_tree(&self) -> Vec<NavNode> {
         self.nav_tree.clone()
     }
 
-    pub async fn get_html_content(&mut self, section: &str, page: &str) -> Result<String, DataError> {
-        let path = self.get_page_path(section, page)?;
-        let markdown = self.get_raw_content(section, page).await?;
-        crate::utils::syntax::ensure_syntaxes_for_markdown(&markdown)
-            .await
-            .map_err(DataError::SyntaxInit)?;
-        Ok(markdown_to_html(&markdown, &path))
+    /// The menu tree without hidden nodes.
+    pub fn nav_tree(&self) -> &[NavNode] {
+        &self.nav_tree
     }
 
-    pub fn find_page(&self, path: &str) -> Option<(&str, &str)> {
-        let search_path = if path.starts_with('/') {
-            path.to_string()
-        } else {
-            format!("/{}", path)
-        };
+    /// The menu tree including hidden nodes, for locating any served page.
+    pub fn site_tree(&self) -> &[NavNode] {
+        &self.site_tree
+    }
+
+    /// Canonical path of every page in the site tree, hidden ones included,
+    /// in menu order.
+    pub fn site_pages(&self) -> Vec<String> {
+        fn walk(db: &JsonDb, nodes: &[NavNode], pages: &mut Vec<String>) {
+            for node in nodes {
+                if node.path().is_empty() {
+                    continue;
+                }
+                if let Some(page) = db.find_page(node.path()) {
+                    if !pages.iter().any(|known| known == page) {
+                        pages.push(page.to_string());
+                    }
+                }
+                if let NavNode::Directory { children, .. } = node {
+                    walk(db, children, pages);
+                }
+            }
+        }
 
+        let mut pages = Vec::new();
+        walk(self, &self.site_tree, &mut pages);
+        pages
+    }
+
+    /// `(name, path)` of every routable page, for search and suggestions.
+    pub fn page_entries(&self) -> Vec<(String, String)> {
         self.pages
-            .iter()
-            .find(|(_, data)| data.path == search_path)
-            .map(|(key, _)| (key.section.as_str(), key.name.as_str()))
-    }
-
-    async fn get_raw_content(
-        &mut self,
-        section: &str,
-        page: &str,
-    ) -> Result<String, DataError> {
-        let key = PageKey {
-            section: section.to_string(),
-            name: page.to_string(),
-        };
+            .values()
+            .map(|page_data| (page_data.name.clone(), page_data.path.clone()))
+            .collect()
+    }
 
-        // Separate the mutable borrow of pages from the async operation
-        let needs_load = {
-            let page_data = self.pages.get_mut(&key)
-                .ok_or(DataError::PageNotFound)?;
+    pub fn revision(&self) -> &str {
+        &self.revision
+    }
 
-            if page_data.raw_content.is_none() {
-                true
-            } else {
-                return Ok(page_data.raw_content.as_deref().unwrap().to_string());
-            }
-        };
+    pub fn page_source(&self, path: &str) -> Result<PageSource, DataError> {
+        let page_data = self.resolve(path).ok_or(DataError::PageNotFound)?;
+        Ok(PageSource {
+            path: page_data.path.clone(),
+            file: Self::source_path(page_data).ok_or(DataError::PageNotFound)?,
+            storage_key: format!("{}-{}", page_data.section, page_data.name),
+            revision: self.revision.clone(),
+        })
+    }
 
-        if needs_load {
-            let content = self.load_content_from_source(section, page).await?;
-            if let Some(page_data) = self.pages.get_mut(&key) {
-                page_data.raw_content = Some(content.clone());
-            }
-            Ok(content)
-        } else {
-            Ok(self.pages[&key].raw_content.as_ref().unwrap().clone())
-        }
+    /// The layout of the closest enclosing section that sets one, falling
+    /// back to the site layout.
+    pub fn layout_for(&self, path: &str) -> Layout {
+        trail(&self.site_tree, path)
+            .iter()
+            .rev()
+            .find_map(|node| self.section_layouts.get(&normalize_path(node.path())))
+            .copied()
+            .unwrap_or(self.site_layout)
     }
 
-    async fn load_content_from_source(
-        &self,
-        section: &str,
-        page: &str,
-    ) -> Result<String, DataError> {
-        let storage_key = format!("{}-{}", section, page);
+    /// Returns the canonical path of the page served at `path`, if any.
+    pub fn find_page(&self, path: &str) -> Option<&str> {
+        self.resolve(path).map(|page_data| page_data.path.as_str())
+    }
 
-        // 1. Check local storage first
-        if let Ok(cached) = LocalStorage::get(&storage_key) {
-            return Ok(cached);
+    /// Follows the redirect map for a path that is not served directly.
+    pub fn redirect_for(&self, path: &str) -> Option<String> {
+        if self.resolve(path).is_some() {
+            return None;
         }
 
-        // 2. Download from network
-        let url = self.get_download_url(section, page)?;
-        let resp = reqwest::get(&url).await?;
-
-        let content = match resp.status() {
-            StatusCode::OK => resp.text().await?,
-            _ => {
-                "".to_string()
+        let mut target = self.redirects.get(&normalize_path(path))?;
+        // Follow chains left behind by repeated moves, giving up on cycles
+        for _ in 0..8 {
+            match self.redirects.get(&normalize_path(target)) {
+                Some(next) if self.resolve(target).is_none() => target = next,
+                _ => return Some(target.clone()),
             }
-        };
-        if !content.is_empty() {
-            LocalStorage::set(&storage_key, &content)?;
         }
-
-        Ok(content)
+        None
     }
 
-    fn get_page_path(&self, section: &str, page: &str) -> Result<String, DataError> {
-        let key = PageKey {
-            section: section.to_string(),
-            name: page.to_string(),
+    /// Turns the `aliases:` of a page's front matter into redirects to it.
+    /// Its `weight:`, `hidden:` and `nav_title:` are left alone: the menus
+    /// only ever follow the index, where `blogy-index` copies them, so they
+    /// do not shift as pages are opened.
+    pub fn register_aliases(&mut self, path: &str, front_matter: &FrontMatter) {
+        let Some(target) = self.find_page(path).map(str::to_string) else {
+            return;
         };
+        for alias in front_matter.aliases() {
+            let alias = normalize_path(&alias);
+            if !self.pages.contains_key(&alias) {
+                self.redirects.insert(alias, target.clone());
+            }
+        }
+    }
 
-        self.pages.get(&key)
-            .map(|data| data.path.clone())
-            .ok_or(DataError::PageNotFound)
+    /// The name the index gives the page served at `path`.
+    pub fn page_name(&self, path: &str) -> Option<&str> {
+        self.resolve(path).map(|page_data| page_data.name.as_str())
     }
 
-    fn get_download_url(&self, section: &str, page: &str) -> Result<String, DataError> {
-        let key = PageKey {
-            section: section.to_string(),
-            name: page.to_string(),
-        };
-        self.pages.get(&key)
-            .and_then(|page_data| {
-                page_data.file.as_ref().map(|file| {
-                    // Construct the full URL properly
-                    if section.to_lowercase() == page_data.path.to_lowercase() {
-                        format!("{}{}/{}", BASE_URL, page_data.path, file)
-                    } else {
-                        format!("{}{}.md", BASE_URL, page_data.path)
-                    }
-                })
-            })
-            .ok_or(DataError::PageNotFound)
+    fn resolve(&self, path: &str) -> Option<&PageData> {
+        let key = normalize_path(path);
+        self.pages.get(&key).or_else(|| {
+            self.aliases
+                .get(&key)
+                .and_then(|target| self.pages.get(target))
+        })
     }
-}
 
-fn markdown_to_html(markdown: &str, path: &str) -> String {
-    let ss = SYNTAX_SET.get().unwrap().read().unwrap_or_else(|e| e.into_inner());
-    let mut sr = ss.find_syntax_plain_text();
-    let mut code = String::new();
-    let mut code_block = false;
-    let theme = &THEME_SET.get().unwrap().themes["base16-ocean.dark"];
-
-    let parser = Parser::new(markdown).filter_map(|event| match event {
-        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) => {
-            sr = ss.find_syntax_by_token(lang.trim()).unwrap_or(sr);
-            code_block = true;
-            None
-        }
-        Event::End(TagEnd::CodeBlock) => {
-            let html = highlighted_html_for_string(&code, &ss, sr, theme).unwrap_or(code.clone());
-            code.clear();
-            code_block = false;
-            Some(Event::Html(html.into()))
-        }
-        Event::Text(t) if code_block => {
-            code.push_str(&t);
-            None
-        }
+    /// Where the markdown of the page served at `path` lives, relative to
+    /// the root of the content repository.
+    pub fn page_file(&self, path: &str) -> Option<String> {
+        self.resolve(path).and_then(Self::source_path)
+    }
 
-        /* ----- image-src rewriter (struct variant) ----- */
-        Event::Start(Tag::Image { link_type: _, dest_url, title, id: _ }) => {
-            let new_dest = if dest_url.starts_with("http") {
-                dest_url.to_string()
+    fn source_path(page_data: &PageData) -> Option<String> {
+        page_data.file.as_ref().map(|file| {
+            // Landing pages, and pages named after their file, are fetched
+            // from the section's folder so the file keeps its own case
+            let in_section = format!("{}/{}", page_data.section, file);
+            let is_landing = page_data.section.to_lowercase() == page_data.path.to_lowercase();
+            let named_after_file = normalize_path(in_section.trim_end_matches(".md"))
+                == normalize_path(&page_data.path);
+            if is_landing || named_after_file {
+                in_section
             } else {
-                let path = path.strip_prefix('/').unwrap_or(path);
-                format!("{BASE_URL}/{path}/{dest_url}")   // <-- your rule
-            };
-            // 2. open the figure and the img tag
-            let mut html = String::new();
-            html.push_str("<figure>");
-            html.push_str(r#"<img src=""#);
-            html.push_str(&html_escape(&new_dest));
-            html.push_str(r#"" alt=""#);
-            html.push_str(&html_escape(&title)); // alt = old title
-            html.push_str("\" />");
-
-            // 3. add caption only if title is non-empty
-            if !title.is_empty() {
-                html.push_str("<figcaption>");
-                html.push_str(&html_escape(&title));
-                html.push_str("</figcaption>");
+                format!("{}.md", page_data.path)
             }
-            html.push_str("</figure>");
-
-            // 4. inject as raw HTML and swallow the original Image event pair
-            Some(Event::Html(html.into()))
-        }
-
-        _ => Some(event),
-    });
+        })
+    }
+}
 
-    let mut html_output = String::new();
-    pulldown_cmark::html::push_html(&mut html_output, parser);
-    html_output
+/// Canonical form of a route path used as lookup key: leading slash, no
+/// trailing or repeated slashes, lowercase.
+pub fn normalize_path(path: &str) -> String {
+    let segments: Vec<&str> = path
+        .trim()
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect();
+    format!("/{}", segments.join("/")).to_lowercase()
 }
 
-fn html_escape(s: &str) -> String {
-    s.replace('&', "&amp;")
-     .replace('<', "&lt;")
-     .replace('>', "&gt;")
-     .replace('"', "&quot;")
+/// A page ready for display: its rendered body and the front matter that
+/// preceded it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedPage {
+    pub path: String,
+    pub html: String,
+    pub front_matter: FrontMatter,
+    pub stats: PageStats,
+    /// Plain text of the opening paragraph.
+    pub excerpt: String,
 }
 
 #[derive(Debug)]
@@ -370,8 +705,9 @@ pub enum DataError {
     Json(serde_json::Error),
     Storage(gloo_storage::errors::StorageError),
     InvalidStructure,
+    InvalidNode { path: String, message: String },
+    UnsupportedVersion(u32),
     PageNotFound,
-    SyntaxInit(String),
 }
 
 impl std::fmt::Display for DataError {
@@ -381,8 +717,9 @@ impl std::fmt::Display for DataError {
             Self::Json(e) => format!("JSON error: {}", e),
             Self::Storage(e) => format!("Storage error: {}", e),
             Self::InvalidStructure => "Invalid data structure".into(),
+            Self::InvalidNode { path, message } => format!("Invalid index node at {path}: {message}"),
+            Self::UnsupportedVersion(v) => format!("Unsupported index version {v} (expected at most {})", crate::utils::index::INDEX_VERSION),
             Self::PageNotFound => "Page not found".into(),
-            Self::SyntaxInit(e) => format!("Syntax highlighter error: {e}"),
         })
     }
 }
@@ -404,3 +741,320 @@ impl From<gloo_storage::errors::StorageError> for DataError {
         Self::Storage(e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::backend::testing::{block_on, StubHttp};
+    use crate::utils::backend::MemoryStorage;
+    use crate::utils::nav_tree::{neighbours, root_entries};
+    use crate::utils::plugins::PluginRegistry;
+    use crate::utils::syntax::Syntaxes;
+
+    const BASE: &str = "https://content.test";
+
+    fn db(json: &str) -> JsonDb {
+        JsonDb::from_index(&parse_index(json).unwrap(), "rev")
+    }
+
+    const SITE: &str = r#"{
+        "version": 1,
+        "root": { "type": "directory", "name": "pages", "path": "/pages", "children": [
+            { "type": "page", "name": "home", "path": "/pages/home", "file": "home.md" },
+            { "type": "page", "name": "new", "path": "/pages/new", "file": "new.md",
+              "aliases": ["/pages/old"] }
+        ]},
+        "redirects": {
+            "/blog/first": "/pages/moved",
+            "/pages/moved": "/pages/new",
+            "/loop/a": "/loop/b",
+            "/loop/b": "/loop/a",
+            "/pages/home": "/pages/new"
+        }
+    }"#;
+
+    const GUIDE: &str = r#"{
+        "root": { "type": "directory", "name": "Pages", "path": "/pages", "children": [
+            { "type": "page", "name": "Home", "path": "/pages/home", "file": "home.md" },
+            { "type": "directory", "name": "Guide", "path": "/pages/guide", "children": [
+                { "type": "page", "name": "Guide", "path": "/pages/guide/guide", "file": "guide.md" },
+                { "type": "page", "name": "Setup", "path": "/pages/guide", "file": "Setup.md" }
+            ]},
+            { "type": "directory", "name": "Notes", "path": "/pages/notes", "children": [
+                { "type": "page", "name": "Intro", "path": "/pages/notes/intro", "file": "intro.md" }
+            ]}
+        ]}
+    }"#;
+
+    #[test]
+    fn paths_normalise_to_one_key() {
+        assert_eq!(normalize_path("pages/Home/"), "/pages/home");
+        assert_eq!(normalize_path(" //Pages//guide///setup "), "/pages/guide/setup");
+        assert_eq!(normalize_path(""), "/");
+        assert_eq!(normalize_path("/"), "/");
+    }
+
+    #[test]
+    fn lookups_ignore_case_and_trailing_slashes() {
+        let db = db(GUIDE);
+        for path in ["/pages/home", "/Pages/HOME", "pages/home/", "//pages//home"] {
+            assert_eq!(db.find_page(path), Some("/pages/home"), "{path}");
+        }
+        assert_eq!(db.find_page("/pages/guide/setup/"), Some("/pages/guide/setup"));
+        assert_eq!(db.page_source("/PAGES/GUIDE/SETUP").unwrap().path, "/pages/guide/setup");
+        // The file keeps its own case whatever the route looks like
+        assert_eq!(db.page_file("/pages/guide/setup").as_deref(), Some("/pages/guide/Setup.md"));
+        assert_eq!(db.find_page("/pages/homes"), None);
+    }
+
+    #[test]
+    fn directories_serve_their_landing_page() {
+        let db = db(GUIDE);
+        assert_eq!(db.find_page("/pages/guide"), Some("/pages/guide/guide"));
+        assert_eq!(db.find_page("/Pages/Guide/"), Some("/pages/guide/guide"));
+        assert_eq!(db.page_source("/pages/guide").unwrap().path, "/pages/guide/guide");
+        // A directory without a landing page serves nothing itself
+        assert_eq!(db.find_page("/pages/notes"), None);
+        assert_eq!(db.find_page("/pages/notes/intro"), Some("/pages/notes/intro"));
+    }
+
+    #[test]
+    fn redirects_follow_chains_and_give_up_on_cycles() {
+        let db = db(SITE);
+        assert_eq!(db.redirect_for("/pages/old").as_deref(), Some("/pages/new"));
+        assert_eq!(db.redirect_for("/Blog/First/").as_deref(), Some("/pages/new"));
+        assert_eq!(db.redirect_for("/loop/a"), None);
+        assert_eq!(db.redirect_for("/nowhere"), None);
+        // A page that exists is served rather than redirected
+        assert_eq!(db.redirect_for("/pages/home"), None);
+    }
+
+    #[test]
+    fn front_matter_aliases_redirect_once_registered() {
+        let mut db = db(SITE);
+        let (front_matter, _) = split_front_matter("---\naliases: [/2019/hello, /pages/new]\n---\n");
+        db.register_aliases("/pages/home", &front_matter);
+
+        assert_eq!(db.redirect_for("/2019/hello").as_deref(), Some("/pages/home"));
+        // An alias never shadows a page of its own
+        assert!(db.find_page("/pages/new").is_some());
+        assert_eq!(db.redirect_for("/pages/new"), None);
+    }
+
+    const MENU: &str = r#"{
+        "root": { "type": "directory", "name": "Pages", "path": "/pages", "children": [
+            { "type": "page", "name": "Home", "path": "/pages/home", "file": "home.md" },
+            { "type": "heading", "name": "Reference" },
+            { "type": "page", "name": "API", "path": "/pages/api", "file": "api.md", "weight": 2 },
+            { "type": "separator" },
+            { "type": "page", "name": "Getting started", "path": "/pages/start", "file": "start.md",
+              "weight": 1, "nav_title": "Start" },
+            { "type": "page", "name": "Imprint", "path": "/pages/imprint", "file": "imprint.md",
+              "hidden": true }
+        ]}
+    }"#;
+
+    fn menu_labels(nodes: &[NavNode]) -> Vec<String> {
+        root_entries(nodes)
+            .1
+            .iter()
+            .map(|node| match node {
+                NavNode::Separator => "---".to_string(),
+                node => node.name().to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn weighted_entries_come_first_and_carry_what_precedes_them() {
+        let db = db(MENU);
+        assert_eq!(
+            menu_labels(db.nav_tree()),
+            ["---", "Start", "Reference", "API", "Home"]
+        );
+    }
+
+    #[test]
+    fn hidden_pages_leave_the_menus_but_stay_routable() {
+        let db = db(MENU);
+        assert!(!menu_labels(db.nav_tree()).contains(&"Imprint".to_string()));
+        assert!(menu_labels(db.site_tree()).contains(&"Imprint".to_string()));
+        assert_eq!(db.find_page("/pages/imprint"), Some("/pages/imprint"));
+        assert!(db.site_pages().contains(&"/pages/imprint".to_string()));
+    }
+
+    #[test]
+    fn opening_a_page_leaves_the_menus_alone() {
+        let mut db = db(MENU);
+        let before = db.nav_tree().to_vec();
+        let (front_matter, _) =
+            split_front_matter("---\nweight: -5\nhidden: true\nnav_title: Landing\n---\n");
+        db.register_aliases("/pages/home", &front_matter);
+        assert_eq!(db.nav_tree(), before);
+
+        // Previous and next follow the same order on every visit
+        let (prev, next) = neighbours(db.nav_tree(), "/pages/api");
+        assert_eq!(prev.map(NavNode::name), Some("Start"));
+        assert_eq!(next.map(NavNode::name), Some("Home"));
+    }
+
+    const COMMITS: &str = "https://api.test/commits";
+
+    fn commits(sha: &str) -> String {
+        format!(r#"[{{ "sha": "{sha}" }}]"#)
+    }
+
+    fn github_loader(http: &StubHttp, storage: MemoryStorage) -> ContentLoader<StubHttp, MemoryStorage> {
+        ContentLoader::new(
+            http.clone(),
+            storage,
+            ContentSource::GitHub { base_url: BASE.to_string(), commits_url: COMMITS.to_string() },
+        )
+    }
+
+    #[test]
+    fn loader_lifecycle_runs_against_stub_backends() {
+        let index = format!("{BASE}/index.json");
+        let home = format!("{BASE}/pages/home.md");
+        let http = StubHttp::default();
+        http.serve(COMMITS, &commits("aaa"))
+            .serve(&index, SITE)
+            .serve(&home, "# Home\n");
+        let loader = github_loader(&http, MemoryStorage::default());
+
+        // First visit: revision, then the index, both remembered
+        assert!(loader.load_cached().is_none());
+        let db = block_on(loader.load()).unwrap();
+        assert_eq!(db.revision(), "aaa");
+        assert_eq!(http.take_requests(), [COMMITS, index.as_str()]);
+        assert_eq!(loader.storage().get(HASH_KEY).as_deref(), Some("aaa"));
+
+        // Same revision: the index comes from storage
+        block_on(loader.load()).unwrap();
+        assert_eq!(http.take_requests(), [COMMITS]);
+        assert_eq!(loader.load_cached().unwrap().revision(), "aaa");
+        assert!(block_on(loader.check_revision()).unwrap().is_none());
+        assert_eq!(http.take_requests(), [COMMITS]);
+
+        // Page fetch: network once, then memory, then storage for a loader
+        // with a cache of its own
+        let source = db.page_source("/pages/home").unwrap();
+        assert_eq!(block_on(loader.fetch_page(&source)).unwrap(), "# Home\n");
+        assert_eq!(block_on(loader.fetch_page(&source)).unwrap(), "# Home\n");
+        assert_eq!(http.take_requests(), [home.as_str()]);
+        let storage = MemoryStorage::default();
+        for key in [HASH_KEY, INDEX_KEY, source.storage_key.as_str()] {
+            storage.set(key, &loader.storage().get(key).unwrap()).unwrap();
+        }
+        let second = github_loader(&http, storage);
+        assert_eq!(block_on(second.fetch_page(&source)).unwrap(), "# Home\n");
+        assert!(http.take_requests().is_empty());
+        assert!(second.cache().raw(&source.path, &source.revision).is_some());
+        assert!(github_loader(&http, MemoryStorage::default())
+            .cache()
+            .raw(&source.path, &source.revision)
+            .is_none());
+
+        // New revision: fetched in the background, stored once applied
+        let moved = SITE.replace(r#""name": "home""#, r#""name": "start""#).replace("/pages/home", "/pages/start");
+        http.serve(COMMITS, &commits("bbb")).serve(&index, &moved);
+        let pending = block_on(loader.check_revision()).unwrap().unwrap();
+        assert_eq!(loader.load_cached().unwrap().revision(), "aaa");
+        let db = loader.apply(pending);
+        assert_eq!(db.revision(), "bbb");
+        assert!(db.find_page("/pages/start").is_some());
+        assert_eq!(loader.load_cached().unwrap().revision(), "bbb");
+        // Pages of the old revision went with it
+        assert!(loader.storage().get(&source.storage_key).is_none());
+    }
+
+    #[test]
+    fn loader_drops_a_broken_index() {
+        let http = StubHttp::default();
+        http.serve(COMMITS, &commits("aaa"))
+            .serve(&format!("{BASE}/index.json"), r#"{ "root": 1 }"#);
+        let loader = github_loader(&http, MemoryStorage::default());
+
+        assert!(block_on(loader.load()).is_err());
+        assert!(loader.storage().get(HASH_KEY).is_none());
+        assert!(loader.load_cached().is_none());
+    }
+
+    #[test]
+    fn dev_server_reports_its_own_revision() {
+        let http = StubHttp::default();
+        http.serve(&format!("{BASE}/revision"), "dev-1-4\n")
+            .serve(&format!("{BASE}/index.json"), SITE);
+        let loader = ContentLoader::new(
+            http.clone(),
+            MemoryStorage::default(),
+            ContentSource::DevServer { url: BASE.to_string() },
+        );
+
+        assert_eq!(block_on(loader.load()).unwrap().revision(), "dev-1-4");
+        assert_eq!(http.take_requests(), [format!("{BASE}/revision"), format!("{BASE}/index.json")]);
+    }
+
+    #[test]
+    fn rendered_pages_are_cached_per_context() {
+        let db = db(SITE);
+        let http = StubHttp::default();
+        http.serve(&format!("{BASE}/pages/home.md"), "![Logo](logo.png)\n");
+        let loader = ContentLoader::new(
+            http,
+            MemoryStorage::default(),
+            ContentSource::DevServer { url: BASE.to_string() },
+        );
+        let source = db.page_source("/pages/home").unwrap();
+
+        let one = RenderContext::new("https://one.test", Default::default());
+        let two = RenderContext::new("https://two.test", Default::default());
+        let first = block_on(loader.render(&source, &one)).unwrap();
+        let second = block_on(loader.render(&source, &two)).unwrap();
+        assert!(first.html.contains("https://one.test/pages/home/logo.png"), "{}", first.html);
+        assert!(second.html.contains("https://two.test/pages/home/logo.png"), "{}", second.html);
+        assert_eq!(block_on(loader.render(&source, &one)).unwrap(), first);
+
+        // A pipeline of its own gets its own copy too
+        let mut plugins = PluginRegistry::builtin();
+        plugins.remove("images");
+        let plain = one.clone().with_plugins(plugins);
+        let third = block_on(loader.render(&source, &plain)).unwrap();
+        assert!(!third.html.contains("<figure>"), "{}", third.html);
+    }
+
+    const TOY_SYNTAX: &str = "%YAML 1.2\n---\nname: Toy\nscope: source.toy\nfile_extensions: [toy]\ncontexts:\n  main:\n    - match: '\\bfn\\b'\n      scope: keyword.toy\n";
+
+    #[test]
+    fn missing_syntaxes_come_through_the_loader_client() {
+        let db = db(SITE);
+        let http = StubHttp::default();
+        http.serve(&format!("{BASE}/pages/home.md"), "```toy\nfn\n```\n\n```nope\nx\n```\n")
+            .serve(&format!("{BASE}/syntax/toy.sublime-syntax"), TOY_SYNTAX);
+        let loader = ContentLoader::new(
+            http.clone(),
+            MemoryStorage::default(),
+            ContentSource::DevServer { url: BASE.to_string() },
+        );
+        let source = db.page_source("/pages/home").unwrap();
+        let ctx = RenderContext::new(BASE, Default::default())
+            .with_syntaxes(Syntaxes::new(Some(format!("{BASE}/syntax"))));
+
+        block_on(loader.render(&source, &ctx)).unwrap();
+        assert_eq!(
+            http.take_requests(),
+            [
+                format!("{BASE}/pages/home.md"),
+                format!("{BASE}/syntax/toy.sublime-syntax"),
+                format!("{BASE}/syntax/nope.sublime-syntax"),
+            ]
+        );
+        assert!(ctx.syntaxes().read().find_syntax_by_token("toy").is_some());
+
+        // Neither is asked for again, found or not
+        let other = RenderContext::new("https://other.test", Default::default())
+            .with_syntaxes(ctx.syntaxes().clone());
+        block_on(loader.render(&source, &other)).unwrap();
+        assert!(http.take_requests().is_empty());
+    }
+}
@@ -1,3 +1,14 @@
+pub mod backend;
+pub mod front_matter;
+pub mod fuzzy;
+pub mod head;
 pub mod hooks;
+pub mod index;
 pub mod json_db;
+pub mod nav_tree;
+pub mod page_cache;
+pub mod page_stats;
+pub mod plugins;
+pub mod render;
+pub mod routing;
 pub mod syntax;
@@ -0,0 +1,126 @@
+use crate::utils::json_db::{normalize_path, NavNode};
+
+/// The chain of nodes from the top of `nodes` down to the node serving
+/// `path`, outermost first. Empty when `path` is not in the tree.
+pub fn trail<'a>(nodes: &'a [NavNode], path: &str) -> Vec<&'a NavNode> {
+    fn walk<'a>(nodes: &'a [NavNode], target: &str, stack: &mut Vec<&'a NavNode>) -> bool {
+        for node in nodes {
+            stack.push(node);
+            if !node.path().is_empty() && normalize_path(node.path()) == target {
+                return true;
+            }
+            if let NavNode::Directory { children, .. } = node {
+                if walk(children, target, stack) {
+                    return true;
+                }
+            }
+            stack.pop();
+        }
+        false
+    }
+
+    let mut stack = Vec::new();
+    walk(nodes, &normalize_path(path), &mut stack);
+    stack
+}
+
+/// The pages listed next to `path` in its directory, in menu order.
+pub fn sibling_pages<'a>(nodes: &'a [NavNode], path: &str) -> Vec<&'a NavNode> {
+    let chain = trail(nodes, path);
+    let parent = chain.len().checked_sub(2).map(|i| chain[i]);
+
+    match parent {
+        Some(NavNode::Directory { children, .. }) => children
+            .iter()
+            .filter(|child| matches!(child, NavNode::Page { .. }))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The pages before and after `path` in its directory, in menu order.
+pub fn neighbours<'a>(nodes: &'a [NavNode], path: &str) -> (Option<&'a NavNode>, Option<&'a NavNode>) {
+    let siblings = sibling_pages(nodes, path);
+    let current = normalize_path(path);
+    let Some(index) = siblings
+        .iter()
+        .position(|node| normalize_path(node.path()) == current)
+    else {
+        return (None, None);
+    };
+    let prev = index.checked_sub(1).map(|i| siblings[i]);
+    (prev, siblings.get(index + 1).copied())
+}
+
+/// What a navigation lists, and the name of the directory it comes from.
+/// The tree normally hangs off one root directory, whose children are
+/// listed; anything else is listed as is.
+pub fn root_entries(nodes: &[NavNode]) -> (Option<&str>, &[NavNode]) {
+    match nodes {
+        [NavNode::Directory { name, children, .. }, ..] => (Some(name), children),
+        _ => (None, nodes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(name: &str, path: &str) -> NavNode {
+        NavNode::Page { name: name.to_string(), path: path.to_string() }
+    }
+
+    fn tree() -> Vec<NavNode> {
+        vec![NavNode::Directory {
+            name: "Pages".to_string(),
+            path: "/pages".to_string(),
+            children: vec![
+                page("Home", "/pages/home"),
+                NavNode::Directory {
+                    name: "Tutorial".to_string(),
+                    path: "/pages/tutorial".to_string(),
+                    children: vec![
+                        page("One", "/pages/tutorial/one"),
+                        NavNode::Separator,
+                        page("Two", "/pages/tutorial/two"),
+                        NavNode::Heading { name: "Extras".to_string() },
+                        page("Three", "/pages/tutorial/three"),
+                    ],
+                },
+            ],
+        }]
+    }
+
+    fn names(nodes: &[&NavNode]) -> Vec<String> {
+        nodes.iter().map(|node| node.name().to_string()).collect()
+    }
+
+    #[test]
+    fn trail_leads_down_to_the_page() {
+        let tree = tree();
+        assert_eq!(names(&trail(&tree, "/Pages/Tutorial/Two/")), ["Pages", "Tutorial", "Two"]);
+        assert!(trail(&tree, "/pages/nowhere").is_empty());
+    }
+
+    #[test]
+    fn siblings_are_the_pages_of_the_same_directory() {
+        let tree = tree();
+        assert_eq!(names(&sibling_pages(&tree, "/pages/tutorial/two")), ["One", "Two", "Three"]);
+        assert_eq!(names(&sibling_pages(&tree, "/pages/home")), ["Home"]);
+        assert!(sibling_pages(&tree, "/pages/nowhere").is_empty());
+    }
+
+    #[test]
+    fn neighbours_skip_separators_and_stop_at_the_ends() {
+        let tree = tree();
+        let (prev, next) = neighbours(&tree, "/pages/tutorial/two");
+        assert_eq!(prev.map(NavNode::name), Some("One"));
+        assert_eq!(next.map(NavNode::name), Some("Three"));
+
+        let (prev, next) = neighbours(&tree, "/pages/tutorial/one");
+        assert_eq!((prev, next.map(NavNode::name)), (None, Some("Two")));
+        let (prev, next) = neighbours(&tree, "/pages/tutorial/three");
+        assert_eq!((prev.map(NavNode::name), next), (Some("Two"), None));
+        assert_eq!(neighbours(&tree, "/pages/home"), (None, None));
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, OnceLock, RwLock};
+
+use crate::utils::render::{RenderContext, RenderedBody};
+
+static PAGE_CACHE: OnceLock<Arc<PageCache>> = OnceLock::new();
+
+/// The cache the app's loaders share.
+pub fn page_cache() -> Arc<PageCache> {
+    PAGE_CACHE.get_or_init(Arc::default).clone()
+}
+
+/// Identifies a rendered page: the same markdown at the same path rendered
+/// with the same context always produces the same HTML. The revision lets
+/// pages of older revisions be dropped along with their markdown.
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct HtmlKey {
+    path: String,
+    revision: String,
+    content_hash: u64,
+    /// `RenderContext::fingerprint` of the context it was rendered with.
+    context: u64,
+}
+
+impl HtmlKey {
+    pub fn new(path: &str, revision: &str, markdown: &str, ctx: &RenderContext) -> Self {
+        Self {
+            path: path.to_string(),
+            revision: revision.to_string(),
+            content_hash: content_hash(markdown),
+            context: ctx.fingerprint(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+struct RawKey {
+    path: String,
+    revision: String,
+}
+
+/// Page markdown and rendered bodies kept in memory. The app's loaders share
+/// `page_cache()`, so revisiting a page skips storage, network and rendering.
+#[derive(Default)]
+pub struct PageCache {
+    raw: RwLock<HashMap<RawKey, String>>,
+    html: RwLock<HashMap<HtmlKey, RenderedBody>>,
+}
+
+impl PageCache {
+    pub fn raw(&self, path: &str, revision: &str) -> Option<String> {
+        let key = RawKey {
+            path: path.to_string(),
+            revision: revision.to_string(),
+        };
+        self.raw
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&key)
+            .cloned()
+    }
+
+    pub fn insert_raw(&self, path: &str, revision: &str, markdown: String) {
+        let key = RawKey {
+            path: path.to_string(),
+            revision: revision.to_string(),
+        };
+        self.raw
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, markdown);
+    }
+
+    pub fn html(&self, key: &HtmlKey) -> Option<RenderedBody> {
+        self.html
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key)
+            .cloned()
+    }
+
+    pub fn insert_html(&self, key: HtmlKey, body: RenderedBody) {
+        self.html
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, body);
+    }
+
+    /// Drops markdown and pages of any revision other than `revision`.
+    pub fn retain_revision(&self, revision: &str) {
+        self.raw
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|key, _| key.revision == revision);
+        self.html
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|key, _| key.revision == revision);
+    }
+}
+
+fn content_hash(markdown: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    markdown.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::page_stats::PageStats;
+
+    #[test]
+    fn a_new_revision_drops_markdown_and_pages_of_the_old_ones() {
+        let cache = PageCache::default();
+        let ctx = RenderContext::new("https://one.test", Default::default());
+        let body = RenderedBody {
+            html: "<p>Old</p>".into(),
+            stats: PageStats::default(),
+            excerpt: "Old".into(),
+        };
+        cache.insert_raw("/pages/home", "aaa", "Old".into());
+        cache.insert_html(HtmlKey::new("/pages/home", "aaa", "Old", &ctx), body.clone());
+        cache.insert_raw("/pages/home", "bbb", "New".into());
+
+        cache.retain_revision("aaa");
+        assert_eq!(cache.raw("/pages/home", "aaa").as_deref(), Some("Old"));
+        assert_eq!(cache.html(&HtmlKey::new("/pages/home", "aaa", "Old", &ctx)), Some(body));
+        assert!(cache.raw("/pages/home", "bbb").is_none());
+
+        cache.retain_revision("bbb");
+        assert!(cache.raw("/pages/home", "aaa").is_none());
+        assert!(cache.html(&HtmlKey::new("/pages/home", "aaa", "Old", &ctx)).is_none());
+    }
+}
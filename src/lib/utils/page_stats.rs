@@ -0,0 +1,36 @@
+/// Typical silent reading speed for prose.
+const WORDS_PER_MINUTE: usize = 200;
+
+/// How much text a page holds, counted while it is rendered. Pages carry
+/// their own in `RenderedPage`; native tools that list pages without
+/// rendering them get the same counts from `summarize`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PageStats {
+    /// Words of prose, code blocks excluded.
+    pub words: usize,
+    /// Words inside code blocks.
+    pub code_words: usize,
+}
+
+impl PageStats {
+    pub fn add_text(&mut self, text: &str) {
+        self.words += count_words(text);
+    }
+
+    pub fn add_code(&mut self, code: &str) {
+        self.code_words += count_words(code);
+    }
+
+    /// Estimated minutes needed to read the prose, never less than one.
+    pub fn reading_minutes(&self) -> usize {
+        self.words.div_ceil(WORDS_PER_MINUTE).max(1)
+    }
+}
+
+/// Whitespace separated runs containing at least one letter or digit, so
+/// stray punctuation and list markers are not counted.
+fn count_words(text: &str) -> usize {
+    text.split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
@@ -0,0 +1,383 @@
+//! Transforms applied to the markdown event stream while a page renders. A
+//! `PluginRegistry` runs its plugins in order, each seeing what the ones
+//! before it let through, before the events are written out as HTML.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use pulldown_cmark::{CodeBlockKind, Event, Tag, TagEnd};
+use syntect::html::highlighted_html_for_string;
+
+use crate::utils::front_matter::FrontMatter;
+use crate::utils::render::RenderContext;
+use crate::utils::routing::resolve_link;
+
+/// The page being rendered.
+#[derive(Debug, Clone, Copy)]
+pub struct PageInfo<'p> {
+    pub path: &'p str,
+    pub front_matter: &'p FrontMatter,
+}
+
+/// One step of the render pipeline. A fresh plugin is made for every page,
+/// so it may keep state between the events of that page.
+pub trait MarkdownPlugin {
+    /// The events that take the place of `event`: itself, a replacement,
+    /// several events or none at all.
+    fn event<'a>(
+        &mut self,
+        event: Event<'a>,
+        page: &PageInfo,
+        ctx: &RenderContext,
+    ) -> Vec<Event<'a>>;
+}
+
+type PluginFactory = Arc<dyn Fn() -> Box<dyn MarkdownPlugin> + Send + Sync>;
+
+/// Hands out the ids of registered plugins.
+static NEXT_PLUGIN_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone)]
+struct Registered {
+    name: String,
+    /// Tells this registration apart from any other, built-ins excepted,
+    /// so a pipeline never reuses HTML cached by another.
+    id: String,
+    make: PluginFactory,
+}
+
+impl Registered {
+    fn new<P, F>(name: &str, make: F) -> Self
+    where
+        P: MarkdownPlugin + 'static,
+        F: Fn() -> P + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            id: format!("{name}#{}", NEXT_PLUGIN_ID.fetch_add(1, Ordering::Relaxed)),
+            make: Arc::new(move || Box::new(make())),
+        }
+    }
+
+    fn builtin<P, F>(name: &str, make: F) -> Self
+    where
+        P: MarkdownPlugin + 'static,
+        F: Fn() -> P + Send + Sync + 'static,
+    {
+        Self {
+            id: name.to_string(),
+            ..Self::new(name, make)
+        }
+    }
+}
+
+/// Named plugins in the order they run.
+#[derive(Clone, Default)]
+pub struct PluginRegistry {
+    plugins: Vec<Registered>,
+}
+
+impl PluginRegistry {
+    /// Internal links, image figures and code highlighting, in that order.
+    pub fn builtin() -> Self {
+        Self {
+            plugins: vec![
+                Registered::builtin("links", InternalLinks::default),
+                Registered::builtin("images", ImageFigures::default),
+                Registered::builtin("highlight", CodeHighlight::default),
+            ],
+        }
+    }
+
+    /// Adds a plugin after the registered ones, replacing any plugin of the
+    /// same name in place. `make` is called once per page.
+    pub fn register<P, F>(&mut self, name: &str, make: F) -> &mut Self
+    where
+        P: MarkdownPlugin + 'static,
+        F: Fn() -> P + Send + Sync + 'static,
+    {
+        let plugin = Registered::new(name, make);
+        match self.position(name) {
+            Some(i) => self.plugins[i] = plugin,
+            None => self.plugins.push(plugin),
+        }
+        self
+    }
+
+    /// Adds a plugin to run just before `before`, or last when there is no
+    /// plugin of that name.
+    pub fn register_before<P, F>(&mut self, before: &str, name: &str, make: F) -> &mut Self
+    where
+        P: MarkdownPlugin + 'static,
+        F: Fn() -> P + Send + Sync + 'static,
+    {
+        self.remove(name);
+        let at = self.position(before).unwrap_or(self.plugins.len());
+        self.plugins.insert(at, Registered::new(name, make));
+        self
+    }
+
+    pub fn remove(&mut self, name: &str) -> &mut Self {
+        self.plugins.retain(|plugin| plugin.name != name);
+        self
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.plugins.iter().map(|plugin| plugin.name.as_str())
+    }
+
+    /// Identifies the pipeline: the same ids in the same order always
+    /// render alike.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.plugins.iter().map(|plugin| plugin.id.as_str())
+    }
+
+    /// Fresh plugins for one page.
+    pub fn start(&self) -> Vec<Box<dyn MarkdownPlugin>> {
+        self.plugins.iter().map(|plugin| (plugin.make)()).collect()
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.plugins.iter().position(|plugin| plugin.name == name)
+    }
+}
+
+/// Points links to other pages of the site at their route, see
+/// `resolve_link`.
+#[derive(Debug, Default)]
+pub struct InternalLinks;
+
+impl MarkdownPlugin for InternalLinks {
+    fn event<'a>(
+        &mut self,
+        event: Event<'a>,
+        page: &PageInfo,
+        ctx: &RenderContext,
+    ) -> Vec<Event<'a>> {
+        match event {
+            Event::Start(Tag::Link { link_type, dest_url, title, id }) => {
+                let dest_url = match resolve_link(&dest_url, page.path) {
+                    Some(route_path) => ctx.href(&route_path).into(),
+                    None => dest_url,
+                };
+                vec![Event::Start(Tag::Link { link_type, dest_url, title, id })]
+            }
+            event => vec![event],
+        }
+    }
+}
+
+/// Renders images as `<figure>`s served from the context's asset base, with
+/// their title as caption.
+#[derive(Debug, Default)]
+pub struct ImageFigures {
+    /// `src` and title of the image being read.
+    image: Option<(String, String)>,
+    alt: String,
+}
+
+impl MarkdownPlugin for ImageFigures {
+    fn event<'a>(
+        &mut self,
+        event: Event<'a>,
+        page: &PageInfo,
+        ctx: &RenderContext,
+    ) -> Vec<Event<'a>> {
+        match event {
+            Event::Start(Tag::Image { dest_url, title, .. }) => {
+                self.image = Some((ctx.image_url(&dest_url, page.path), title.to_string()));
+                self.alt.clear();
+                Vec::new()
+            }
+            Event::End(TagEnd::Image) => {
+                let Some((src, title)) = self.image.take() else {
+                    return Vec::new();
+                };
+                let alt = if self.alt.is_empty() { &title } else { &self.alt };
+
+                let mut html = String::new();
+                html.push_str("<figure>");
+                html.push_str(r#"<img src=""#);
+                html.push_str(&html_escape(&src));
+                html.push_str(r#"" alt=""#);
+                html.push_str(&html_escape(alt));
+                html.push_str("\" />");
+
+                // Caption only if the title is non-empty
+                if !title.is_empty() {
+                    html.push_str("<figcaption>");
+                    html.push_str(&html_escape(&title));
+                    html.push_str("</figcaption>");
+                }
+                html.push_str("</figure>");
+                vec![Event::Html(html.into())]
+            }
+            // The alt text, swallowed with the rest of the image
+            Event::Text(text) | Event::Code(text) if self.image.is_some() => {
+                self.alt.push_str(&text);
+                Vec::new()
+            }
+            _ if self.image.is_some() => Vec::new(),
+            event => vec![event],
+        }
+    }
+}
+
+/// Highlights fenced code blocks with the context's syntaxes and theme;
+/// indented blocks are left as they are.
+#[derive(Debug, Default)]
+pub struct CodeHighlight {
+    /// Language of the fenced block being read.
+    lang: Option<String>,
+    code: String,
+}
+
+impl MarkdownPlugin for CodeHighlight {
+    fn event<'a>(
+        &mut self,
+        event: Event<'a>,
+        _page: &PageInfo,
+        ctx: &RenderContext,
+    ) -> Vec<Event<'a>> {
+        match event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) => {
+                self.lang = Some(lang.trim().to_string());
+                Vec::new()
+            }
+            Event::Text(text) if self.lang.is_some() => {
+                self.code.push_str(&text);
+                Vec::new()
+            }
+            Event::End(TagEnd::CodeBlock) if self.lang.is_some() => {
+                let lang = self.lang.take().unwrap_or_default();
+                let code = std::mem::take(&mut self.code);
+                let ss = ctx.syntaxes().read();
+                let syntax = ss
+                    .find_syntax_by_token(&lang)
+                    .unwrap_or_else(|| ss.find_syntax_plain_text());
+                let html = highlighted_html_for_string(&code, &ss, syntax, ctx.theme())
+                    .unwrap_or_else(|_| format!("<pre><code>{}</code></pre>", html_escape(&code)));
+                vec![Event::Html(html.into())]
+            }
+            event => vec![event],
+        }
+    }
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+     .replace('<', "&lt;")
+     .replace('>', "&gt;")
+     .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use pulldown_cmark::CowStr;
+
+    use super::*;
+    use crate::utils::render::{markdown_to_html, RenderOptions};
+
+    /// Puts every heading in a `<section>`, expanding one event into two.
+    #[derive(Default)]
+    struct Sections {
+        open: bool,
+    }
+
+    impl MarkdownPlugin for Sections {
+        fn event<'a>(&mut self, event: Event<'a>, _: &PageInfo, _: &RenderContext) -> Vec<Event<'a>> {
+            match event {
+                Event::Start(Tag::Heading { .. }) => {
+                    let open = Event::Html(CowStr::Borrowed("<section>"));
+                    let events = if self.open {
+                        vec![Event::Html(CowStr::Borrowed("</section>")), open, event]
+                    } else {
+                        vec![open, event]
+                    };
+                    self.open = true;
+                    events
+                }
+                event => vec![event],
+            }
+        }
+    }
+
+    /// Drops every image before the built-in plugin sees it.
+    struct NoImages;
+
+    impl MarkdownPlugin for NoImages {
+        fn event<'a>(&mut self, event: Event<'a>, _: &PageInfo, _: &RenderContext) -> Vec<Event<'a>> {
+            match event {
+                Event::Start(Tag::Image { .. }) | Event::End(TagEnd::Image) => Vec::new(),
+                event => vec![event],
+            }
+        }
+    }
+
+    fn render(markdown: &str, ctx: &RenderContext) -> String {
+        let front_matter = FrontMatter::default();
+        let page = PageInfo { path: "/pages/post", front_matter: &front_matter };
+        markdown_to_html(markdown, &page, ctx).html
+    }
+
+    fn context() -> RenderContext {
+        RenderContext::new("https://assets.test", RenderOptions::default())
+    }
+
+    #[test]
+    fn builtins_rewrite_links_images_and_code() {
+        let html = render(
+            "[Up](../index.md#top) ![A *cat*](cat.png \"Our cat\")\n\n```nope\na < b\n```\n",
+            &context(),
+        );
+        assert!(html.contains(r#"<a href="/index#top">Up</a>"#), "{html}");
+        assert!(
+            html.contains(r#"<figure><img src="https://assets.test/pages/post/cat.png" alt="A cat" /><figcaption>Our cat</figcaption></figure>"#),
+            "{html}"
+        );
+        assert!(html.contains("a &lt; b"), "{html}");
+        assert!(!html.contains("<code"), "unhighlighted block left in {html}");
+    }
+
+    #[test]
+    fn plugins_can_expand_events_and_run_in_order() {
+        let mut plugins = PluginRegistry::builtin();
+        plugins
+            .register("sections", Sections::default)
+            .register_before("images", "no-images", || NoImages);
+        assert_eq!(
+            plugins.names().collect::<Vec<_>>(),
+            ["links", "no-images", "images", "highlight", "sections"]
+        );
+
+        let ctx = context().with_plugins(plugins.clone());
+        let html = render("# One\n\n![Gone](gone.png)\n\n# Two\n", &ctx);
+        assert_eq!(html.matches("<section>").count(), 2, "{html}");
+        assert_eq!(html.matches("</section>").count(), 1, "{html}");
+        assert!(!html.contains("figure") && !html.contains("gone.png"), "{html}");
+        // The alt text is left as plain text once the image is gone
+        assert!(html.contains("Gone"), "{html}");
+
+        plugins.remove("no-images");
+        let html = render("![Back](back.png)\n", &context().with_plugins(plugins));
+        assert!(html.contains("<figure>"), "{html}");
+    }
+
+    #[test]
+    fn replaced_plugins_change_the_fingerprint() {
+        let builtin = context();
+        assert_eq!(builtin.fingerprint(), context().fingerprint());
+
+        let mut plugins = PluginRegistry::builtin();
+        plugins.register("images", || NoImages);
+        let custom = context().with_plugins(plugins.clone());
+        assert_eq!(custom.plugins().names().collect::<Vec<_>>(), ["links", "images", "highlight"]);
+        assert_ne!(builtin.fingerprint(), custom.fingerprint());
+        // The same registry keeps its fingerprint, a fresh registration does not
+        assert_eq!(custom.fingerprint(), context().with_plugins(plugins).fingerprint());
+
+        let mut again = PluginRegistry::builtin();
+        again.register("images", || NoImages);
+        assert_ne!(custom.fingerprint(), context().with_plugins(again).fingerprint());
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use syntect::highlighting::{Theme, ThemeSet};
+
+use crate::content_base;
+use crate::utils::page_stats::PageStats;
+use crate::utils::plugins::{PageInfo, PluginRegistry};
+use crate::utils::routing::{base_prefix, href_for, HASH_ROUTING};
+use crate::utils::syntax::{Syntaxes, THEME_NAME};
+
+/// How pages become HTML beyond what their markdown says.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    /// Path the app is served below, put in front of internal links.
+    pub base_path: String,
+    /// Point internal links into the URL fragment, see `HASH_ROUTING`.
+    pub hash_routing: bool,
+    /// Markdown extensions to parse with.
+    pub markdown: Options,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            base_path: String::new(),
+            hash_routing: false,
+            markdown: Options::empty(),
+        }
+    }
+}
+
+impl RenderOptions {
+    /// The routing of the running app.
+    pub fn app() -> Self {
+        Self {
+            base_path: base_prefix(),
+            hash_routing: HASH_ROUTING,
+            ..Self::default()
+        }
+    }
+}
+
+/// Everything `markdown_to_html` needs besides the page itself. Cheap to
+/// clone, and clones share the syntaxes loaded so far.
+#[derive(Clone)]
+pub struct RenderContext {
+    syntaxes: Syntaxes,
+    theme_name: String,
+    theme: Arc<Theme>,
+    /// Where relative image paths are served from.
+    asset_base: String,
+    options: RenderOptions,
+    plugins: PluginRegistry,
+}
+
+impl RenderContext {
+    /// Plain-text highlighting in the default theme and the built-in
+    /// plugins, with images served below `asset_base`.
+    pub fn new(asset_base: &str, options: RenderOptions) -> Self {
+        let theme = ThemeSet::load_defaults()
+            .themes
+            .remove(THEME_NAME)
+            .unwrap_or_default();
+        Self {
+            syntaxes: Syntaxes::new(None),
+            theme_name: THEME_NAME.to_string(),
+            theme: Arc::new(theme),
+            asset_base: asset_base.trim_end_matches('/').to_string(),
+            options,
+            plugins: PluginRegistry::builtin(),
+        }
+    }
+
+    /// What the app renders with: images from the content source and extra
+    /// syntaxes from its own `assets/syntax` folder.
+    pub fn app() -> Self {
+        let syntax_url = web_sys::window()
+            .and_then(|window| window.location().origin().ok())
+            .map(|origin| format!("{origin}{}/assets/syntax", base_prefix()));
+        Self::new(content_base(), RenderOptions::app()).with_syntaxes(Syntaxes::new(syntax_url))
+    }
+
+    pub fn with_syntaxes(mut self, syntaxes: Syntaxes) -> Self {
+        self.syntaxes = syntaxes;
+        self
+    }
+
+    pub fn with_theme(mut self, name: &str, theme: Theme) -> Self {
+        self.theme_name = name.to_string();
+        self.theme = Arc::new(theme);
+        self
+    }
+
+    pub fn with_plugins(mut self, plugins: PluginRegistry) -> Self {
+        self.plugins = plugins;
+        self
+    }
+
+    pub fn syntaxes(&self) -> &Syntaxes {
+        &self.syntaxes
+    }
+
+    pub fn theme_name(&self) -> &str {
+        &self.theme_name
+    }
+
+    /// Identifies the settings a page is rendered with, so contexts that
+    /// would render it differently never share a cached copy.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.theme_name.hash(&mut hasher);
+        self.asset_base.hash(&mut hasher);
+        self.syntaxes.url().hash(&mut hasher);
+        self.options.base_path.hash(&mut hasher);
+        self.options.hash_routing.hash(&mut hasher);
+        self.options.markdown.bits().hash(&mut hasher);
+        for id in self.plugins.ids() {
+            id.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    pub fn options(&self) -> &RenderOptions {
+        &self.options
+    }
+
+    pub fn plugins(&self) -> &PluginRegistry {
+        &self.plugins
+    }
+
+    /// Where an image referenced by the page at `path` is served from.
+    pub fn image_url(&self, dest: &str, path: &str) -> String {
+        match image_source(dest, path) {
+            Some(source) => format!("{}/{source}", self.asset_base),
+            None => dest.to_string(),
+        }
+    }
+
+    /// The `href` for a route path inside the site.
+    pub fn href(&self, path: &str) -> String {
+        href_for(path, &self.options.base_path, self.options.hash_routing)
+    }
+}
+
+/// The output of `markdown_to_html`: the page body and what it holds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedBody {
+    pub html: String,
+    pub stats: PageStats,
+    pub excerpt: String,
+}
+
+/// What a page holds, without its HTML.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageSummary {
+    pub stats: PageStats,
+    /// Plain text of the opening paragraph.
+    pub excerpt: String,
+}
+
+/// Collects a `PageSummary` from the markdown events as written.
+#[derive(Default)]
+struct Summarizer {
+    summary: PageSummary,
+    in_excerpt: bool,
+    in_code: bool,
+}
+
+impl Summarizer {
+    fn observe(&mut self, event: &Event) {
+        let PageSummary { stats, excerpt } = &mut self.summary;
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => self.in_code = true,
+            Event::End(TagEnd::CodeBlock) => self.in_code = false,
+            Event::Text(t) if self.in_code => stats.add_code(t),
+
+            /* ----- word count and excerpt ----- */
+            Event::Start(Tag::Paragraph) if excerpt.is_empty() => self.in_excerpt = true,
+            Event::End(TagEnd::Paragraph) => self.in_excerpt = false,
+            Event::SoftBreak | Event::HardBreak if self.in_excerpt => excerpt.push(' '),
+            Event::Text(t) | Event::Code(t) => {
+                stats.add_text(t);
+                if self.in_excerpt {
+                    excerpt.push_str(t);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Word counts and excerpt of a page, for when its HTML is not needed.
+pub fn summarize(markdown: &str, options: &RenderOptions) -> PageSummary {
+    let mut summarizer = Summarizer::default();
+    Parser::new_ext(markdown, options.markdown).for_each(|event| summarizer.observe(&event));
+    summarizer.summary
+}
+
+/// Renders a page body: counts its words and takes its excerpt from the
+/// markdown as written, then runs the context's plugins over it.
+pub fn markdown_to_html(markdown: &str, page: &PageInfo, ctx: &RenderContext) -> RenderedBody {
+    let mut summarizer = Summarizer::default();
+    let events = Parser::new_ext(markdown, ctx.options.markdown)
+        .inspect(|event| summarizer.observe(event));
+
+    // Each plugin sees every event the ones before it let through
+    let mut plugins = ctx.plugins.start();
+    let events = events.flat_map(|event| {
+        plugins.iter_mut().fold(vec![event], |events, plugin| {
+            events
+                .into_iter()
+                .flat_map(|event| plugin.event(event, page, ctx))
+                .collect()
+        })
+    });
+
+    let mut html_output = String::new();
+    pulldown_cmark::html::push_html(&mut html_output, events);
+    let PageSummary { stats, excerpt } = summarizer.summary;
+    RenderedBody { html: html_output, stats, excerpt }
+}
+
+/// The image's file relative to the content root, or `None` when `dest` is
+/// a full URL.
+pub fn image_source(dest: &str, path: &str) -> Option<String> {
+    if dest.starts_with("http") {
+        return None;
+    }
+    let path = path.strip_prefix('/').unwrap_or(path);
+    Some(format!("{path}/{dest}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::front_matter::FrontMatter;
+
+    fn render(markdown: &str, ctx: &RenderContext) -> String {
+        let front_matter = FrontMatter::default();
+        let page = PageInfo { path: "/pages/post", front_matter: &front_matter };
+        markdown_to_html(markdown, &page, ctx).html
+    }
+
+    #[test]
+    fn contexts_render_with_their_own_settings() {
+        let markdown = "![Cat](cat.png)\n\n[Next](next.md)\n";
+        let first = RenderContext::new("https://one.test/", RenderOptions::default());
+        let second = RenderContext::new(
+            "https://two.test",
+            RenderOptions { base_path: "/blog".into(), hash_routing: true, ..Default::default() },
+        );
+
+        let html = render(markdown, &first);
+        assert!(html.contains(r#"src="https://one.test/pages/post/cat.png""#), "{html}");
+        assert!(html.contains(r#"href="/pages/next""#), "{html}");
+
+        let html = render(markdown, &second);
+        assert!(html.contains(r#"src="https://two.test/pages/post/cat.png""#), "{html}");
+        assert!(html.contains(r##"href="#/pages/next""##), "{html}");
+    }
+
+    #[test]
+    fn fingerprint_follows_the_settings() {
+        let options = RenderOptions::default();
+        let ctx = RenderContext::new("https://one.test", options.clone());
+        assert_eq!(ctx.fingerprint(), RenderContext::new("https://one.test", options.clone()).fingerprint());
+        assert_ne!(ctx.fingerprint(), RenderContext::new("https://two.test", options.clone()).fingerprint());
+
+        let tables = RenderOptions { markdown: Options::ENABLE_TABLES, ..options };
+        assert_ne!(ctx.fingerprint(), RenderContext::new("https://one.test", tables).fingerprint());
+        let themed = ctx.clone().with_theme("plain", Theme::default());
+        assert_ne!(ctx.fingerprint(), themed.fingerprint());
+    }
+
+    #[test]
+    fn stats_and_excerpt_come_from_the_markdown() {
+        let ctx = RenderContext::new("https://one.test", RenderOptions::default());
+        let front_matter = FrontMatter::default();
+        let page = PageInfo { path: "/pages/post", front_matter: &front_matter };
+        let body = markdown_to_html(
+            "# Title\n\nFirst *line*\nsecond line.\n\n```rust\nlet x = 1;\n```\n",
+            &page,
+            &ctx,
+        );
+        assert_eq!(body.excerpt, "First line second line.");
+        assert_eq!(body.stats.words, 5);
+        assert_eq!(body.stats.code_words, 3);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::Route;
+
+/// Whether the router keeps the route in the URL fragment (`/#/pages/home`)
+/// instead of the path, for static hosts that cannot serve deep links.
+pub const HASH_ROUTING: bool = cfg!(feature = "hash-router");
+
+pub fn path_to_route(path: &str) -> Option<Route> {
+    let trimmed_path = path.trim_start_matches('#').trim_start_matches('/');
+
+    if trimmed_path.is_empty() {
+        return Some(home_route());
+    }
+
+    let segments: Vec<String> = trimmed_path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect();
+
+    if !segments.is_empty() {
+        Some(Route::PageContent { path: segments })
+    } else {
+        None
+    }
+}
+
+pub fn home_route() -> Route {
+    Route::PageContent {
+        path: vec!["pages".to_string(), "home".to_string()],
+    }
+}
+
+/// The app's base path from `Dioxus.toml` as a `/prefix`, or empty.
+pub fn base_prefix() -> String {
+    dioxus::cli_config::base_path()
+        .filter(|path| !path.is_empty())
+        .map(|path| format!("/{}", path.trim_matches('/')))
+        .unwrap_or_default()
+}
+
+/// The `href` for a route path in raw HTML that the router does not render,
+/// such as links inside page content, for an app served below `base` and
+/// routing with or without the URL fragment.
+pub fn href_for(path: &str, base: &str, hash_routing: bool) -> String {
+    let path = format!("/{}", path.trim_start_matches('#').trim_start_matches('/'));
+    if hash_routing {
+        return format!("#{path}");
+    }
+
+    if base.is_empty() || path == base || path.starts_with(&format!("{base}/")) {
+        path
+    } else {
+        format!("{base}{path}")
+    }
+}
+
+/// Resolves a markdown link target to a route path when it points inside the
+/// site, relative to the page at `page_path`, keeping any `#anchor`. External
+/// URLs, `mailto:` and in-page `#anchor` links are left alone.
+pub fn resolve_link(dest: &str, page_path: &str) -> Option<String> {
+    if let Some(route) = dest.strip_prefix("#/") {
+        return Some(format!("/{route}"));
+    }
+    if dest.is_empty() || dest.starts_with('#') || dest.starts_with("//") || dest.contains(':') {
+        return None;
+    }
+
+    let (dest, anchor) = match dest.split_once('#') {
+        Some((dest, anchor)) => (dest, format!("#{anchor}")),
+        None => (dest, String::new()),
+    };
+    let target = dest.strip_suffix(".md").unwrap_or(dest);
+    let mut segments: Vec<&str> = if target.starts_with('/') {
+        Vec::new()
+    } else {
+        // Relative to the directory holding the current page
+        let mut parent: Vec<&str> = page_path.split('/').filter(|s| !s.is_empty()).collect();
+        parent.pop();
+        parent
+    };
+
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    Some(format!("/{}{anchor}", segments.join("/")))
+}
+
+/// Absolute URL of a route path on the origin the app is served from.
+pub fn absolute_url(path: &str) -> Option<String> {
+    let origin = web_sys::window()?.location().origin().ok()?;
+    Some(site_url(&format!("{origin}{}", base_prefix()), path, HASH_ROUTING))
+}
+
+/// Absolute URL of a route path for an app served at `site_root`, the
+/// origin followed by the base path.
+pub fn site_url(site_root: &str, path: &str, hash_routing: bool) -> String {
+    let site_root = site_root.trim_end_matches('/');
+    let path = format!("/{}", path.trim_start_matches('#').trim_start_matches('/'));
+    if hash_routing {
+        format!("{site_root}/#{path}")
+    } else {
+        format!("{site_root}{path}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn site_urls_follow_the_routing_mode() {
+        let root = "https://example.test/blog";
+        assert_eq!(site_url(root, "/pages/home", false), "https://example.test/blog/pages/home");
+        assert_eq!(site_url(root, "/pages/home", true), "https://example.test/blog/#/pages/home");
+        assert_eq!(site_url("https://example.test/", "pages/home", true), "https://example.test/#/pages/home");
+    }
+
+    #[test]
+    fn hrefs_carry_the_base_path_once() {
+        assert_eq!(href_for("/pages/home", "/blog", false), "/blog/pages/home");
+        assert_eq!(href_for("/blog/pages/home", "/blog", false), "/blog/pages/home");
+        assert_eq!(href_for("pages/home", "", false), "/pages/home");
+        assert_eq!(href_for("/pages/home", "/blog", true), "#/pages/home");
+    }
+
+    #[test]
+    fn links_resolve_against_the_page_directory() {
+        let page = "/pages/guide/intro";
+        assert_eq!(resolve_link("setup.md", page).as_deref(), Some("/pages/guide/setup"));
+        assert_eq!(resolve_link("../about.md#team", page).as_deref(), Some("/pages/about#team"));
+        assert_eq!(resolve_link("/pages/home", page).as_deref(), Some("/pages/home"));
+        assert_eq!(resolve_link("#/pages/home", page).as_deref(), Some("/pages/home"));
+        assert_eq!(resolve_link("#top", page), None);
+        assert_eq!(resolve_link("https://example.test", page), None);
+        assert_eq!(resolve_link("mailto:me@example.test", page), None);
+    }
+}
@@ -1,32 +1,88 @@
 use std::collections::HashSet;
-use std::sync::{OnceLock, RwLock};
+use std::sync::{Arc, RwLock, RwLockReadGuard};
 
-use crate::{SYNTAX_SET, THEME_SET};
 use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
-use syntect::{
-    highlighting::ThemeSet,
-    parsing::{SyntaxDefinition, SyntaxSet, SyntaxSetBuilder},
-};
+use syntect::parsing::{SyntaxDefinition, SyntaxSet, SyntaxSetBuilder};
 
-static ATTEMPTED_LANGS: OnceLock<RwLock<HashSet<String>>> = OnceLock::new();
+use crate::utils::backend::HttpClient;
 
-fn attempted_langs() -> &'static RwLock<HashSet<String>> {
-    ATTEMPTED_LANGS.get_or_init(|| RwLock::new(HashSet::new()))
+pub const THEME_NAME: &str = "base16-ocean.dark";
+
+/// The syntaxes code blocks are highlighted with. Starts with plain text and
+/// grows as pages ask for languages, whose `.sublime-syntax` definitions are
+/// fetched from `url` when there is one, through the client of whoever asks.
+/// Clones share what has been loaded.
+#[derive(Clone)]
+pub struct Syntaxes {
+    set: Arc<RwLock<SyntaxSet>>,
+    attempted: Arc<RwLock<HashSet<String>>>,
+    url: Option<String>,
 }
 
-fn syntax_asset_url(lang: &str) -> Result<String, String> {
-    let window = web_sys::window().ok_or("no window")?;
-    let origin = window
-        .location()
-        .origin()
-        .map_err(|_| "no origin".to_string())?;
+impl Syntaxes {
+    pub fn new(url: Option<String>) -> Self {
+        let mut builder = SyntaxSetBuilder::new();
+        builder.add_plain_text_syntax();
+
+        Self {
+            set: Arc::new(RwLock::new(builder.build())),
+            attempted: Arc::new(RwLock::new(HashSet::new())),
+            url,
+        }
+    }
+
+    /// Where missing syntaxes are fetched from.
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, SyntaxSet> {
+        self.set.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub async fn ensure_for_markdown(&self, http: &impl HttpClient, markdown: &str) {
+        for lang in collect_code_languages(markdown) {
+            self.ensure(http, &lang).await;
+        }
+    }
+
+    /// Loads the syntax for `lang` unless it is known or was tried before.
+    /// A language that cannot be loaded is highlighted as plain text.
+    async fn ensure(&self, http: &impl HttpClient, lang: &str) {
+        if syntax_loaded(&self.read(), lang) || self.was_attempted(lang) {
+            return;
+        }
+        if let Some(syntax) = self.fetch(http, lang).await {
+            let mut ss = self.set.write().unwrap_or_else(|e| e.into_inner());
+            let mut builder = ss.clone().into_builder();
+            builder.add(syntax);
+            *ss = builder.build();
+        }
+        self.mark_attempted(lang);
+    }
+
+    async fn fetch(&self, http: &impl HttpClient, lang: &str) -> Option<SyntaxDefinition> {
+        let url = format!("{}/{lang}.sublime-syntax", self.url.as_deref()?);
+        let response = http.get(&url, &[]).await.ok()?;
+        if !response.is_success() {
+            return None;
+        }
+        SyntaxDefinition::load_from_str(&response.body, true, None).ok()
+    }
 
-    let base = dioxus::cli_config::base_path()
-        .filter(|path| !path.is_empty())
-        .map(|path| format!("/{}", path.trim_matches('/')))
-        .unwrap_or_default();
+    fn mark_attempted(&self, lang: &str) {
+        self.attempted
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(lang.to_string());
+    }
 
-    Ok(format!("{origin}{base}/assets/syntax/{lang}.sublime-syntax"))
+    fn was_attempted(&self, lang: &str) -> bool {
+        self.attempted
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(lang)
+    }
 }
 
 fn sanitize_lang(lang: &str) -> Option<String> {
@@ -60,105 +116,3 @@ pub fn collect_code_languages(markdown: &str) -> Vec<String> {
 fn syntax_loaded(ss: &SyntaxSet, lang: &str) -> bool {
     ss.find_syntax_by_token(lang).is_some()
 }
-
-fn mark_attempted(lang: &str) {
-    attempted_langs()
-        .write()
-        .unwrap_or_else(|e| e.into_inner())
-        .insert(lang.to_string());
-}
-
-fn was_attempted(lang: &str) -> bool {
-    attempted_langs()
-        .read()
-        .unwrap_or_else(|e| e.into_inner())
-        .contains(lang)
-}
-
-async fn ensure_syntax(lang: &str) -> Result<(), String> {
-    {
-        let ss = SYNTAX_SET
-            .get()
-            .ok_or("syntax highlighter not initialized")?
-            .read()
-            .unwrap_or_else(|e| e.into_inner());
-        if syntax_loaded(&ss, lang) {
-            return Ok(());
-        }
-    }
-
-    if was_attempted(lang) {
-        return Ok(());
-    }
-
-    let url = syntax_asset_url(lang)?;
-    let response = match reqwest::get(&url).await {
-        Ok(response) => response,
-        Err(_) => {
-            mark_attempted(lang);
-            return Ok(());
-        }
-    };
-
-    if response.status() == reqwest::StatusCode::NOT_FOUND {
-        mark_attempted(lang);
-        return Ok(());
-    }
-
-    let content = match response.error_for_status() {
-        Ok(response) => match response.text().await {
-            Ok(content) => content,
-            Err(_) => {
-                mark_attempted(lang);
-                return Ok(());
-            }
-        },
-        Err(_) => {
-            mark_attempted(lang);
-            return Ok(());
-        }
-    };
-
-    let syntax = match SyntaxDefinition::load_from_str(&content, true, None) {
-        Ok(syntax) => syntax,
-        Err(_) => {
-            mark_attempted(lang);
-            return Ok(());
-        }
-    };
-
-    {
-        let mut ss = SYNTAX_SET
-            .get()
-            .ok_or("syntax highlighter not initialized")?
-            .write()
-            .unwrap_or_else(|e| e.into_inner());
-        let mut builder = ss.clone().into_builder();
-        builder.add(syntax);
-        *ss = builder.build();
-    }
-
-    mark_attempted(lang);
-    Ok(())
-}
-
-pub async fn ensure_syntaxes_for_markdown(markdown: &str) -> Result<(), String> {
-    for lang in collect_code_languages(markdown) {
-        ensure_syntax(&lang).await?;
-    }
-    Ok(())
-}
-
-pub async fn init_syntax_highlighter() -> Result<(), String> {
-    let mut builder = SyntaxSetBuilder::new();
-    builder.add_plain_text_syntax();
-
-    SYNTAX_SET.get_or_init(|| RwLock::new(builder.build()));
-    attempted_langs();
-
-    THEME_SET
-        .set(ThemeSet::load_defaults())
-        .map_err(|_| "theme set already initialized".to_string())?;
-
-    Ok(())
-}
@@ -13,5 +13,17 @@ use ui::App;
 fn main() {
     logger::init(Level::INFO).expect("failed to init logger");
     tracing::debug!("Rendering app!");
+
+    #[cfg(feature = "hash-router")]
+    {
+        use std::rc::Rc;
+
+        let history = Rc::new(dioxus::web::HashHistory::new(true));
+        LaunchBuilder::web()
+            .with_cfg(dioxus::web::Config::new().history(history))
+            .launch(App);
+    }
+
+    #[cfg(not(feature = "hash-router"))]
     launch(App);
 }